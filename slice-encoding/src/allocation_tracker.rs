@@ -0,0 +1,334 @@
+// Copyright (c) ZeroC, Inc.
+
+//! Types for keeping track of how much heap memory decoders have allocated.
+//!
+//! By default, each [`Decoder`](crate::decoder::Decoder) owns its own tracker, so every payload gets a fresh allocation
+//! budget. To enforce a single budget across many payloads (for example all the requests received over a connection),
+//! create a tracker up-front, and have each decoder borrow it with
+//! [`Decoder::with_allocation_tracker`](crate::decoder::Decoder::with_allocation_tracker), or share it with
+//! [`Decoder::with_shared_allocation_tracker`](crate::decoder::Decoder::with_shared_allocation_tracker).
+
+use crate::decoder::DecodeErrorKind;
+
+use alloc::rc::Rc;
+use core::cell::Cell;
+use core::fmt::Debug;
+
+#[cfg(target_has_atomic = "ptr")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Setting this value as a tracker's limit disables it, allowing for arbitrary amounts of heap memory to be allocated.
+///
+/// This is generally a dangerous thing to do, as it allows malformed or malicious payloads to exhaust resources.
+pub(crate) const NO_LIMIT: usize = usize::MAX;
+
+/// Keeps a running total of how much heap memory has been allocated, and enforces a limit on that total.
+///
+/// All of this trait's functions take `&self`, so that a single tracker can be borrowed by multiple decoders at once.
+/// Implementations are responsible for providing whatever interior mutability they need to update their total.
+pub trait AllocationTracker: Debug {
+    /// Returns the maximum amount of heap memory (in bytes) this tracker allows to be allocated.
+    fn limit(&self) -> usize;
+
+    /// Returns the amount of heap memory (in bytes) that has been allocated so far.
+    fn total(&self) -> usize;
+
+    /// Returns the amount of heap memory (in bytes) that can be allocated before hitting this tracker's limit.
+    ///
+    /// If this tracker's limit has been disabled, this always returns [`usize::MAX`].
+    fn remaining(&self) -> usize {
+        match self.limit() {
+            NO_LIMIT => NO_LIMIT,
+            limit => limit.saturating_sub(self.total()),
+        }
+    }
+
+    /// Attempts to add `size` bytes to this tracker's running total.
+    ///
+    /// If the new total would exceed this tracker's limit, the total is left unchanged, and a
+    /// [`HeapAllocationLimitReached`](DecodeErrorKind::HeapAllocationLimitReached) error is returned instead.
+    fn try_increase_total(&self, size: usize) -> Result<(), DecodeErrorKind>;
+
+    /// Subtracts `size` bytes from this tracker's running total, saturating at zero.
+    ///
+    /// This should be called once memory that was counted against this tracker has been freed, so that long-lived
+    /// trackers (such as one shared by every payload received over a connection) don't only ever grow.
+    fn release(&self, size: usize);
+}
+
+/// Returns the error that trackers report when an allocation of `requested` bytes would exceed their `limit`.
+fn limit_reached(limit: usize, current: usize, requested: usize) -> DecodeErrorKind {
+    DecodeErrorKind::HeapAllocationLimitReached { limit, current, requested }
+}
+
+/// A plain allocation tracker, for use by a single thread.
+///
+/// This is the tracker that decoders create for themselves when they're given an explicit limit.
+///
+/// # Examples
+///
+/// ```
+/// # use slice_encoding::allocation_tracker::{AllocationTracker, LocalAllocationTracker};
+/// let tracker = LocalAllocationTracker::new(16);
+///
+/// assert!(tracker.try_increase_total(10).is_ok());
+/// assert_eq!(tracker.remaining(), 6);
+///
+/// // `10 + 10 > 16`, so this fails, and the total is left unchanged.
+/// assert!(tracker.try_increase_total(10).is_err());
+/// assert_eq!(tracker.total(), 10);
+/// ```
+#[derive(Debug)]
+pub struct LocalAllocationTracker {
+    total: Cell<usize>,
+    limit: usize,
+}
+
+impl LocalAllocationTracker {
+    /// Creates a new tracker which allows up to `limit` bytes of heap memory to be allocated.
+    pub fn new(limit: usize) -> Self {
+        LocalAllocationTracker { total: Cell::new(0), limit }
+    }
+
+    /// Creates a new tracker with no limit, allowing for arbitrary amounts of heap memory to be allocated.
+    /// This is generally a dangerous thing to do, as it allows malformed or malicious payloads to exhaust resources.
+    pub fn unlimited() -> Self {
+        Self::new(NO_LIMIT)
+    }
+}
+
+impl AllocationTracker for LocalAllocationTracker {
+    fn limit(&self) -> usize {
+        self.limit
+    }
+
+    fn total(&self) -> usize {
+        self.total.get()
+    }
+
+    fn try_increase_total(&self, size: usize) -> Result<(), DecodeErrorKind> {
+        // Unlimited trackers don't bother keeping a running total.
+        if self.limit == NO_LIMIT {
+            return Ok(());
+        }
+
+        let current = self.total.get();
+        match current.checked_add(size) {
+            Some(new_total) if new_total <= self.limit => {
+                self.total.set(new_total);
+                Ok(())
+            }
+            _ => Err(limit_reached(self.limit, current, size)),
+        }
+    }
+
+    fn release(&self, size: usize) {
+        self.total.set(self.total.get().saturating_sub(size));
+    }
+}
+
+/// The allocation tracker that decoders create for themselves when they aren't provided with a limit.
+///
+/// Instead of a fixed number of bytes, this tracker limits how many elements can be allocated, and allows each of
+/// them to be as large as the largest element that has been allocated so far. Its limit in bytes is
+/// `element_limit * largest_element_size`, and only ever grows.
+///
+/// This way, its limit depends on the shape of a payload, instead of the in-memory size of the types it's decoded
+/// into. See [`DEFAULT_ALLOCATION_LIMIT_FACTOR`](crate::decoder::DEFAULT_ALLOCATION_LIMIT_FACTOR) for more details.
+#[derive(Debug)]
+pub(crate) struct ElementAllocationTracker {
+    total: Cell<usize>,
+    element_limit: usize,
+    largest_element_size: Cell<usize>,
+}
+
+impl ElementAllocationTracker {
+    /// Creates a new tracker which allows up to `element_limit` elements to be allocated.
+    pub(crate) fn new(element_limit: usize) -> Self {
+        ElementAllocationTracker {
+            total: Cell::new(0),
+            element_limit,
+            largest_element_size: Cell::new(1),
+        }
+    }
+
+    /// Records that an element which takes up `size` bytes of heap memory is about to be allocated.
+    /// This must be called before the element's size is added to this tracker's total.
+    pub(crate) fn record_element_size(&self, size: usize) {
+        if size > self.largest_element_size.get() {
+            self.largest_element_size.set(size);
+        }
+    }
+}
+
+impl AllocationTracker for ElementAllocationTracker {
+    fn limit(&self) -> usize {
+        self.element_limit.saturating_mul(self.largest_element_size.get())
+    }
+
+    fn total(&self) -> usize {
+        self.total.get()
+    }
+
+    fn try_increase_total(&self, size: usize) -> Result<(), DecodeErrorKind> {
+        let limit = self.limit();
+        let current = self.total.get();
+        match current.checked_add(size) {
+            Some(new_total) if new_total <= limit => {
+                self.total.set(new_total);
+                Ok(())
+            }
+            _ => Err(limit_reached(limit, current, size)),
+        }
+    }
+
+    fn release(&self, size: usize) {
+        self.total.set(self.total.get().saturating_sub(size));
+    }
+}
+
+/// An allocation tracker that can be safely shared between threads.
+///
+/// This is useful for enforcing a single allocation budget across decoders running on different threads, by sharing
+/// the tracker through a `&'static` reference or an [`Arc`](alloc::sync::Arc).
+///
+/// # Examples
+///
+/// ```
+/// # use slice_encoding::allocation_tracker::{AllocationTracker, AtomicAllocationTracker};
+/// # use std::sync::Arc;
+/// let tracker = Arc::new(AtomicAllocationTracker::new(64));
+///
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let tracker = Arc::clone(&tracker);
+///         std::thread::spawn(move || tracker.try_increase_total(16).is_ok())
+///     })
+///     .collect();
+///
+/// // All 4 threads were able to allocate their 16 bytes, which exhausts the budget.
+/// assert!(handles.into_iter().all(|handle| handle.join().unwrap()));
+/// assert_eq!(tracker.remaining(), 0);
+/// ```
+#[cfg(target_has_atomic = "ptr")]
+#[derive(Debug)]
+pub struct AtomicAllocationTracker {
+    total: AtomicUsize,
+    limit: usize,
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl AtomicAllocationTracker {
+    /// Creates a new tracker which allows up to `limit` bytes of heap memory to be allocated.
+    pub fn new(limit: usize) -> Self {
+        AtomicAllocationTracker { total: AtomicUsize::new(0), limit }
+    }
+
+    /// Creates a new tracker with no limit, allowing for arbitrary amounts of heap memory to be allocated.
+    /// This is generally a dangerous thing to do, as it allows malformed or malicious payloads to exhaust resources.
+    pub fn unlimited() -> Self {
+        Self::new(NO_LIMIT)
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl AllocationTracker for AtomicAllocationTracker {
+    fn limit(&self) -> usize {
+        self.limit
+    }
+
+    fn total(&self) -> usize {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    fn try_increase_total(&self, size: usize) -> Result<(), DecodeErrorKind> {
+        // Unlimited trackers don't bother keeping a running total.
+        if self.limit == NO_LIMIT {
+            return Ok(());
+        }
+
+        // The total is only a counter, it doesn't guard any other memory, so relaxed ordering is sufficient.
+        self.total
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+                current.checked_add(size).filter(|new_total| *new_total <= self.limit)
+            })
+            .map(|_| ())
+            .map_err(|current| limit_reached(self.limit, current, size))
+    }
+
+    fn release(&self, size: usize) {
+        // `fetch_update` only fails if the closure returns `None`, which this one never does.
+        let _ = self.total.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+            Some(current.saturating_sub(size))
+        });
+    }
+}
+
+/// A cheaply cloneable handle to an allocation tracker, for use by a single thread.
+///
+/// All the clones of a handle share the same running total and limit. Decoders can hold onto a clone of their own (see
+/// [`Decoder::with_shared_allocation_tracker`](crate::decoder::Decoder::with_shared_allocation_tracker)), so unlike
+/// a borrowed tracker, the tracker doesn't need to outlive them. This makes it easy to hand out the same budget to
+/// every decoder created on a connection.
+///
+/// Since the budget is shared by every payload, memory should be given back to it with
+/// [`release`](AllocationTracker::release) once the values decoded from a payload are dropped. Otherwise, its total
+/// only ever grows, and the connection eventually runs out of budget.
+///
+/// # Examples
+///
+/// ```
+/// # use slice_encoding::allocation_tracker::{AllocationTracker, SharedAllocationTracker};
+/// # use slice_encoding::decoder::Decoder;
+/// # use slice_encoding::slice2::Slice2;
+/// let connection_budget = SharedAllocationTracker::new(16);
+///
+/// // Each payload is decoded with its own decoder, but they all draw from the same budget.
+/// let mut strings = Vec::new();
+/// for payload in [[8_u8, 0x61, 0x62], [8, 0x63, 0x64]] {
+///     let mut decoder: Decoder<Slice2> = Decoder::new(&payload).with_shared_allocation_tracker(&connection_budget);
+///     strings.push(decoder.try_decode::<String>().unwrap());
+/// }
+/// assert_eq!(connection_budget.total(), 4);
+///
+/// // Once the decoded strings are dropped, their memory is given back to the budget.
+/// for string in strings {
+///     connection_budget.release(string.len());
+/// }
+/// assert_eq!(connection_budget.total(), 0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SharedAllocationTracker {
+    inner: Rc<LocalAllocationTracker>,
+}
+
+impl SharedAllocationTracker {
+    /// Creates a new tracker which allows up to `limit` bytes of heap memory to be allocated.
+    pub fn new(limit: usize) -> Self {
+        SharedAllocationTracker { inner: Rc::new(LocalAllocationTracker::new(limit)) }
+    }
+
+    /// Creates a new tracker with no limit, allowing for arbitrary amounts of heap memory to be allocated.
+    /// This is generally a dangerous thing to do, as it allows malformed or malicious payloads to exhaust resources.
+    pub fn unlimited() -> Self {
+        Self::new(NO_LIMIT)
+    }
+}
+
+impl AllocationTracker for SharedAllocationTracker {
+    fn limit(&self) -> usize {
+        self.inner.limit()
+    }
+
+    fn total(&self) -> usize {
+        self.inner.total()
+    }
+
+    fn try_increase_total(&self, size: usize) -> Result<(), DecodeErrorKind> {
+        self.inner.try_increase_total(size)
+    }
+
+    fn release(&self, size: usize) {
+        self.inner.release(size)
+    }
+}
@@ -1,39 +1,223 @@
 // Copyright (c) ZeroC, Inc.
 
 use crate::Encoding;
-use crate::try_decode::TryDecode;
+use crate::io_types::{self, BufferedInput};
+use crate::try_decode::{TryDecode, TryDecodeBorrowed};
+use crate::try_skip::TrySkip;
 
-/// Setting this value for [`maximum_allowed_heap_allocation_size`](Decoder::maximum_allowed_heap_allocation_size)
-/// disables the decoder's built-in allocation limit, allowing for it to allocate arbitrary amounts of heap memory.
+#[cfg(feature = "alloc")]
+use crate::allocation_tracker::{
+    AllocationTracker, ElementAllocationTracker, LocalAllocationTracker, SharedAllocationTracker,
+};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::iter::Rev;
+#[cfg(feature = "alloc")]
+use core::slice;
+
+/// The factor used to compute a decoder's allocation limit when one isn't explicitly provided.
+///
+/// Newly created decoders are allowed to allocate up to `DEFAULT_ALLOCATION_LIMIT_FACTOR` elements per byte in their
+/// input, where each element is charged as much heap memory as the largest element they've allocated so far. Every
+/// element of a well-formed payload takes up at least one byte of input, except for unset optional elements, which
+/// take up a single bit. So well-formed payloads never reach this limit, no matter how large the types they're decoded
+/// into are, while a small malicious payload still can't trigger huge allocations.
+///
+/// See [`Decoder`'s default allocation limits](Decoder#default-allocation-limits) for when this is used.
+#[cfg(feature = "alloc")]
+pub const DEFAULT_ALLOCATION_LIMIT_FACTOR: usize = 8;
+
+/// The allocation limit used by decoders which read from a stream, when one isn't explicitly provided.
+///
+/// Streams can't know how many bytes are in their input ahead of time, so their allocation limit can't be computed
+/// with [`DEFAULT_ALLOCATION_LIMIT_FACTOR`]. Instead, they use this fixed limit of 16 MiB. See
+/// [`Decoder`'s default allocation limits](Decoder#default-allocation-limits) for more details.
+#[cfg(feature = "std")]
+pub const DEFAULT_STREAMING_ALLOCATION_LIMIT: usize = 16 * 1024 * 1024;
+
+/// The maximum nesting depth that decoders allow when one isn't explicitly provided.
 ///
-/// This is generally a dangerous thing to do, as it allows malformed or malicious payloads to exhaust resources.
-const NO_LIMIT: usize = usize::MAX;
+/// This is deep enough for any reasonable payload, while still keeping a malicious payload from overflowing the stack
+/// with deeply nested values. It can be overridden on a per-decoder basis with [`Decoder::with_max_depth`].
+pub const DEFAULT_MAX_DEPTH: usize = 100;
 
-/// TODO
+/// Decodes values from bytes which were encoded with the Slice encoding `E`.
+///
+/// # Default allocation limits
+///
+/// Decoders limit how much heap memory they allocate, to keep malformed or malicious payloads from exhausting system
+/// resources. Unless one is explicitly provided, a decoder's limit depends on its input:
+/// - Decoders over a buffer (created with [`new`](Self::new) or `from_bytes`) are allowed to allocate up to
+///   [`DEFAULT_ALLOCATION_LIMIT_FACTOR`] elements per byte of their input, where each element is charged as much heap
+///   memory as the largest element they've allocated so far. This grows with the element types being decoded.
+/// - Decoders over a stream (created with `from_reader`) can't know how many bytes their input holds, so they use a
+///   fixed limit of `DEFAULT_STREAMING_ALLOCATION_LIMIT` bytes instead. The memory used to store the bytes they read
+///   is charged against this limit too.
+///
+/// These defaults can be overridden with [`with_allocation_limit`](Self::with_allocation_limit),
+/// [`with_proportional_allocation_limit`](Self::with_proportional_allocation_limit),
+/// [`with_no_allocation_limit`](Self::with_no_allocation_limit), or
+/// [`with_allocation_tracker`](Self::with_allocation_tracker).
 #[derive(Debug)]
 pub struct Decoder<'a, E: Encoding> {
     /// Which version of the Slice encoding this decoder is using.
     encoding: E,
 
-    buffer_temp: core::marker::PhantomData<&'a ()>, // TODO
+    /// The input that this decoder reads its bytes from. It tracks the decoder's current position in the input.
+    input: BufferedInput<'a>,
+
+    /// Whether this decoder only accepts the canonical encoding of each value. See [`Self::with_strict_mode`].
+    strict: bool,
+
+    /// Whether this decoder reports running out of bytes as an incomplete input. See [`Self::with_incremental_mode`].
+    incremental: bool,
 
-    /// Stores a running total of how much heap memory this decoder has allocated.
+    /// How many nested values this decoder is currently in the middle of decoding. See [`Self::enter`].
+    depth: usize,
+
+    /// The maximum nesting depth this decoder allows. See [`Self::enter`].
+    max_depth: usize,
+
+    /// Keeps a running total of how much heap memory this decoder has allocated, and the limit on that total.
     ///
     /// This information is used to prevent malformed or malicious payloads from triggering huge allocations, or abusing
     /// system resources. If the decoder attempts to allocate memory which would cause this running total to exceed
-    /// [`maximum_allowed_heap_allocation_size`](Self::maximum_allowed_heap_allocation_size), a [`DecodeError`] is
-    /// returned instead of performing the allocation.
+    /// the tracker's limit, a [`DecodeError`] is returned instead of performing the allocation.
     #[cfg(feature = "alloc")]
-    total_heap_allocation_size: usize,
+    allocation_tracker: DecoderAllocationTracker<'a>,
 
-    /// Stores the maximum amount of heap memory this decoder is allowed to allocate.
-    /// See [`total_heap_allocation_size`](Self::total_heap_allocation_size) for more information.
-    #[cfg(feature = "alloc")]
-    maximum_allowed_heap_allocation_size: usize,
+    /// How many of the bytes that this decoder's input has allocated have been charged against its allocation tracker.
+    /// See [`Self::charge_input_storage`].
+    #[cfg(feature = "std")]
+    charged_input_storage: usize,
+}
+
+/// The allocation tracker used by a decoder. Either one owned by the decoder itself, or one borrowed from the caller.
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+enum DecoderAllocationTracker<'a> {
+    /// The tracker used when no limit is explicitly provided. See [`DEFAULT_ALLOCATION_LIMIT_FACTOR`].
+    Default(ElementAllocationTracker),
+    Owned(LocalAllocationTracker),
+    Shared(SharedAllocationTracker),
+    Borrowed(&'a dyn AllocationTracker),
+}
+
+#[cfg(feature = "alloc")]
+impl DecoderAllocationTracker<'_> {
+    fn get(&self) -> &dyn AllocationTracker {
+        match self {
+            Self::Default(tracker) => tracker,
+            Self::Owned(tracker) => tracker,
+            Self::Shared(tracker) => tracker,
+            Self::Borrowed(tracker) => *tracker,
+        }
+    }
 }
 
 impl<'a, E: Encoding> Decoder<'a, E> {
-    // TODO new function here?
+    /// Creates a new decoder that reads bytes from the provided buffer, starting at the beginning of the buffer.
+    ///
+    /// The decoder's allocation limit is initially set to [`DEFAULT_ALLOCATION_LIMIT_FACTOR`] times the length of
+    /// `buffer`, and grows with the size of the elements it allocates. See
+    /// [default allocation limits](Self#default-allocation-limits).
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+    #[cfg_attr(feature = "alloc", doc = "```")]
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let buffer: &[u8] = &[1, 2, 3];
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+    ///
+    /// assert_eq!(decoder.remaining(), 3);
+    /// assert_eq!(decoder.remaining_heap_allocation_size(), 24);
+    /// ```
+    pub fn new(buffer: &'a [u8]) -> Self {
+        Self::from(BufferedInput::from(buffer))
+    }
+
+    /// Creates a new decoder that reads bytes from the provided [`Bytes`], starting at the beginning of the buffer.
+    ///
+    /// This behaves exactly like [`new`](Self::new), except that decoding a [`Bytes`] from this decoder returns a
+    /// reference-counted slice of `bytes`, instead of copying it into a new buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bytes::Bytes;
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// // A sequence of 3 bytes: `[1, 2, 3]`.
+    /// let bytes = Bytes::from_static(&[12, 1, 2, 3]);
+    /// let mut decoder: Decoder<Slice2> = Decoder::from_bytes(&bytes);
+    ///
+    /// let sequence: Bytes = decoder.try_decode().unwrap();
+    /// assert_eq!(sequence, &[1, 2, 3][..]);
+    ///
+    /// // The decoded sequence shares its memory with the original buffer.
+    /// assert_eq!(sequence.as_ptr(), bytes[1..].as_ptr());
+    /// ```
+    ///
+    /// [`Bytes`]: bytes::Bytes
+    #[cfg(feature = "bytes")]
+    pub fn from_bytes(bytes: &'a bytes::Bytes) -> Self {
+        Self::from(BufferedInput::from(bytes))
+    }
+
+    /// Creates a new decoder that reads bytes from the provided reader as they're needed, instead of requiring its
+    /// entire input up-front. Bytes are stored in `storage`, which values borrowed from this decoder's input point into.
+    /// See [`ReadBuffer`] for more information.
+    ///
+    /// Since the decoder can't know how many bytes are remaining in a reader, [`remaining`](Self::remaining) always
+    /// returns [`usize::MAX`], and the decoder's allocation limit is initially set to
+    /// [`DEFAULT_STREAMING_ALLOCATION_LIMIT`]. See [default allocation limits](Self#default-allocation-limits).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::io_types::reader::ReadBuffer;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let reader = std::io::Cursor::new(vec![1, 2, 3]);
+    /// let buffer = ReadBuffer::new();
+    /// let mut decoder: Decoder<Slice2> = Decoder::from_reader(reader, &buffer);
+    ///
+    /// assert_eq!(decoder.read_bytes_exact(2), Ok(&[1, 2][..]));
+    /// assert_eq!(decoder.read_byte(), Ok(&3));
+    /// assert!(decoder.read_byte().is_err());
+    /// ```
+    ///
+    /// [`ReadBuffer`]: crate::io_types::reader::ReadBuffer
+    #[cfg(feature = "std")]
+    pub fn from_reader(reader: impl std::io::Read + 'a, storage: &'a io_types::reader::ReadBuffer) -> Self {
+        Self::from(BufferedInput::from_reader(reader, storage))
+    }
+
+    /// Returns the version of the Slice encoding this decoder is using.
+    pub fn encoding(&self) -> E {
+        self.encoding
+    }
+
+    /// Returns the number of bytes this decoder has consumed from its input so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let buffer: &[u8] = &[1, 2, 3];
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+    ///
+    /// assert_eq!(decoder.position(), 0);
+    /// assert_eq!(decoder.read_bytes_exact(2), Ok(&[1, 2][..]));
+    /// assert_eq!(decoder.position(), 2);
+    /// ```
+    pub fn position(&self) -> usize {
+        self.input.position()
+    }
 
     /// Sets this decoder's allocation limit to the the provided value, then returns it by value.
     ///
@@ -43,6 +227,8 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// # Examples
     ///
     /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
     /// let buffer: &[u8] = &[0; 16];
     /// let decoder: Decoder<Slice2> = Decoder::new(buffer).with_allocation_limit(16);
     ///
@@ -50,17 +236,25 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// ```
     #[cfg(feature = "alloc")]
     pub fn with_allocation_limit(mut self, limit: usize) -> Self {
-        self.maximum_allowed_heap_allocation_size = limit;
+        self.allocation_tracker = DecoderAllocationTracker::Owned(LocalAllocationTracker::new(limit));
         self
     }
 
-    /// Sets this decoder's allocation limit to `factor` multiplied by the number of bytes remaining in 
-    /// Sets this decoder's allocation limit using the following expression: `limit = factor * remaining`.
-    /// TODO
+    /// Sets this decoder's allocation limit to `factor` multiplied by the number of bytes remaining in its input, then
+    /// returns it by value. If this multiplication overflows, the limit saturates at `usize::MAX` instead.
+    ///
+    /// Decoders that read from a stream can't know how many bytes are remaining in their input, so multiplying by it
+    /// would leave them without any limit at all. Instead, their limit is set to `DEFAULT_STREAMING_ALLOCATION_LIMIT`,
+    /// regardless of `factor`. Use [`with_allocation_limit`](Self::with_allocation_limit) to pick a different limit.
+    ///
+    /// For more information on how the allocation limit works, see:
+    /// [`increase_heap_allocation_total`](Self::increase_heap_allocation_total)
     ///
     /// # Examples
     ///
     /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
     /// let buffer: &[u8] = &[0; 16];
     /// let decoder: Decoder<Slice2> = Decoder::new(buffer).with_proportional_allocation_limit(4);
     ///
@@ -68,6 +262,8 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// ```
     ///
     /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
     /// let buffer: &[u8] = &[0; 12];
     /// let decoder: Decoder<Slice2> = Decoder::new(buffer).with_proportional_allocation_limit(10);
     ///
@@ -75,8 +271,13 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// ```
     #[cfg(feature = "alloc")]
     pub fn with_proportional_allocation_limit(mut self, factor: usize) -> Self {
-        // TODO self.maximum_allowed_heap_allocation_size = ...
-        self
+        #[cfg(feature = "std")]
+        if self.input.is_streaming() {
+            return self.with_allocation_limit(DEFAULT_STREAMING_ALLOCATION_LIMIT);
+        }
+
+        let limit = factor.saturating_mul(self.remaining());
+        self.with_allocation_limit(limit)
     }
 
     /// Disables this decoder's allocation limit check, then returns it by value.
@@ -88,8 +289,10 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// # Examples
     ///
     /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
     /// let buffer: &[u8] = &[0; 16];
-    /// let decoder: Decoder<Slice2> = Decoder::new(buffer).with_no_allocation_limit();
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer).with_no_allocation_limit();
     ///
     /// assert_eq!(decoder.remaining_heap_allocation_size(), usize::MAX);
     ///
@@ -100,15 +303,310 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// ```
     #[cfg(feature = "alloc")]
     pub fn with_no_allocation_limit(mut self) -> Self {
-        self.maximum_allowed_heap_allocation_size = NO_LIMIT;
+        self.allocation_tracker = DecoderAllocationTracker::Owned(LocalAllocationTracker::unlimited());
+        self
+    }
+
+    /// Makes this decoder use the provided allocation tracker instead of its own, then returns it by value.
+    ///
+    /// Any allocations made by this decoder count against the tracker's limit, as do any allocations made by other
+    /// decoders sharing the same tracker. This allows a single memory cap to be enforced across many payloads.
+    ///
+    /// For more information on how the allocation limit works, see:
+    /// [`increase_heap_allocation_total`](Self::increase_heap_allocation_total)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::allocation_tracker::{AllocationTracker, LocalAllocationTracker};
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let tracker = LocalAllocationTracker::new(16);
+    ///
+    /// let mut first: Decoder<Slice2> = Decoder::new(&[]).with_allocation_tracker(&tracker);
+    /// assert!(first.increase_heap_allocation_total(10).is_ok());
+    ///
+    /// // The second decoder shares the first decoder's budget, so it only has 6 bytes left.
+    /// let mut second: Decoder<Slice2> = Decoder::new(&[]).with_allocation_tracker(&tracker);
+    /// assert_eq!(second.remaining_heap_allocation_size(), 6);
+    /// assert!(second.increase_heap_allocation_total(10).is_err());
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn with_allocation_tracker(mut self, tracker: &'a dyn AllocationTracker) -> Self {
+        self.allocation_tracker = DecoderAllocationTracker::Borrowed(tracker);
         self
     }
 
+    /// Makes this decoder use a clone of the provided shared allocation tracker instead of its own, then returns it by
+    /// value.
+    ///
+    /// This behaves like [`with_allocation_tracker`](Self::with_allocation_tracker), except that this decoder holds
+    /// onto its own handle to the tracker, instead of borrowing it. See [`SharedAllocationTracker`] for more
+    /// information.
+    #[cfg(feature = "alloc")]
+    pub fn with_shared_allocation_tracker(mut self, tracker: &SharedAllocationTracker) -> Self {
+        self.allocation_tracker = DecoderAllocationTracker::Shared(tracker.clone());
+        self
+    }
+
+    /// Sets the maximum nesting depth this decoder allows to the provided value, then returns it by value.
+    ///
+    /// For more information on how the depth limit works, see: [`enter`](Self::enter)
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+    #[cfg_attr(feature = "alloc", doc = "```")]
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// // A sequence holding a sequence holding a sequence holding nothing: `[[[]]]`.
+    /// let buffer: &[u8] = &[4, 4, 0];
+    ///
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer).with_max_depth(3);
+    /// assert!(decoder.try_decode::<Vec<Vec<Vec<u8>>>>().is_ok());
+    ///
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer).with_max_depth(2);
+    /// assert!(decoder.try_decode::<Vec<Vec<Vec<u8>>>>().is_err());
+    /// ```
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Enables strict mode on this decoder, then returns it by value.
+    ///
+    /// By default, decoders accept some values which have more than one valid encoding. In strict mode, decoders only
+    /// accept the canonical (shortest) encoding of each value, so that every value has exactly one byte representation.
+    /// Specifically, strict decoders reject:
+    /// - variable-length integers and sizes which aren't encoded on the minimum number of bytes
+    /// - bit sequences whose unused bits aren't zero
+    /// - dictionaries which contain duplicate keys, and sets which contain duplicate elements
+    /// - trailing bytes after the end of the input (see [`finish`](Self::finish))
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// // The varuint62 `1`, needlessly encoded on 2 bytes.
+    /// let buffer: &[u8] = &[0b0000_0101, 0];
+    ///
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+    /// assert_eq!(decoder.try_decode_varuint62(), Ok(1));
+    ///
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer).with_strict_mode();
+    /// assert!(decoder.try_decode_varuint62().is_err());
+    /// ```
+    pub fn with_strict_mode(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Returns `true` if this decoder is in strict mode. See [`Self::with_strict_mode`].
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Enables incremental mode on this decoder, then returns it by value.
+    ///
+    /// By default, decoders report running out of bytes as [`EndOfBuffer`](DecodeErrorKind::EndOfBuffer), since the
+    /// input is assumed to be complete. In incremental mode, decoders instead assume that their input may only be a
+    /// prefix of the full payload (such as a partially received network message), and report running out of bytes as
+    /// [`Incomplete`](DecodeErrorKind::Incomplete), which says how many more bytes are needed.
+    ///
+    /// Once more bytes are available, decoding can be retried with a new decoder, starting from a [`Checkpoint`] which
+    /// was taken before the incomplete value. Values before the checkpoint don't need to be decoded again.
+    ///
+    /// Note that a decoder's default allocation limit is computed from the number of bytes in its input when it's
+    /// created. Since an incremental decoder's input is only part of the payload, it should usually be given an
+    /// explicit limit instead (see [`Self::with_allocation_limit`]).
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+    #[cfg_attr(feature = "alloc", doc = "```")]
+    /// # use slice_encoding::decoder::{Decoder, DecodeErrorKind};
+    /// # use slice_encoding::slice2::Slice2;
+    /// // The first fragment of a payload holding a bool, followed by the string `"hi"`.
+    /// let mut received = vec![1, 8, b'h'];
+    ///
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(&received).with_incremental_mode();
+    /// assert_eq!(decoder.try_decode::<bool>(), Ok(true));
+    /// let checkpoint = decoder.checkpoint();
+    ///
+    /// let error = decoder.try_decode::<String>().unwrap_err();
+    /// assert_eq!(error.kind(), &DecodeErrorKind::Incomplete { needed: 1 });
+    ///
+    /// // Once the rest of the payload arrives, we resume from the checkpoint, without decoding the bool again.
+    /// // The old decoder borrows `received`, so it has to be dropped before we can add to it.
+    /// drop(decoder);
+    /// received.push(b'i');
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(&received).with_incremental_mode();
+    /// decoder.resume_from(checkpoint).unwrap();
+    /// assert_eq!(decoder.try_decode::<String>(), Ok("hi".to_owned()));
+    /// ```
+    pub fn with_incremental_mode(mut self) -> Self {
+        self.incremental = true;
+        self
+    }
+
+    /// Returns `true` if this decoder is in incremental mode. See [`Self::with_incremental_mode`].
+    pub fn is_incremental(&self) -> bool {
+        self.incremental
+    }
+
+    /// Returns a checkpoint which records this decoder's current position in its input.
+    /// See [`Self::resume_from`] for more information.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint { position: self.position() }
+    }
+
+    /// Moves this decoder to the position recorded by the provided checkpoint.
+    ///
+    /// The checkpoint can be taken from this decoder, or from another decoder whose input was a prefix of this one's.
+    /// Moving forwards consumes the bytes between this decoder's position and the checkpoint without decoding them.
+    /// Moving backwards is only possible if those bytes are still buffered by this decoder's input. This is always the
+    /// case for slices, but streaming inputs only keep the bytes they've read since last refilling.
+    ///
+    /// If this decoder's input doesn't reach the checkpoint, or the checkpoint can't be rewound to, `Err` is returned,
+    /// and this decoder's position is left unchanged.
+    pub fn resume_from(&mut self, checkpoint: Checkpoint) -> DecodeResult<()> {
+        let position = self.position();
+        match checkpoint.position.checked_sub(position) {
+            Some(count) => self.read_bytes_exact(count).map(|_| ()),
+            None if self.input.rewind(checkpoint.position) => Ok(()),
+            None => {
+                let kind = DecodeErrorKind::InvalidData {
+                    desc: "cannot rewind to a checkpoint before the start of the input's buffered bytes",
+                };
+                Err(DecodeError::new(kind, position))
+            }
+        }
+    }
+
+    /// Consumes this decoder, checking that it has finished decoding its input.
+    ///
+    /// In strict mode, this returns `Err` if there are any bytes remaining in the decoder's input, since trailing bytes
+    /// mean that the input isn't the canonical encoding of whatever was decoded from it.
+    /// Otherwise, this always returns `Ok`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let buffer: &[u8] = &[1, 2];
+    ///
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer).with_strict_mode();
+    /// assert_eq!(decoder.try_decode::<bool>(), Ok(true));
+    /// assert!(decoder.finish().is_err());
+    ///
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+    /// assert_eq!(decoder.try_decode::<bool>(), Ok(true));
+    /// assert!(decoder.finish().is_ok());
+    /// ```
+    pub fn finish(mut self) -> DecodeResult<()> {
+        // We peek instead of checking `remaining`, since streaming inputs don't know how many bytes they have left.
+        self.limit_input_storage();
+        if self.strict && self.input.peek_byte().is_ok() {
+            let kind = DecodeErrorKind::InvalidData { desc: "encountered trailing bytes after the end of the input" };
+            return Err(DecodeError::new(kind, self.position()));
+        }
+        Ok(())
+    }
+
     /// TODO
     pub fn try_decode<T: TryDecode<E>>(&mut self) -> DecodeResult<T> {
         T::try_decode(self)
     }
 
+    /// Decodes a value which borrows its data directly from this decoder's input, instead of copying it.
+    /// See [`TryDecodeBorrowed`] for more information.
+    pub fn try_decode_borrowed<T: TryDecodeBorrowed<'a, E>>(&mut self) -> DecodeResult<T> {
+        T::try_decode_borrowed(self)
+    }
+
+    /// Advances this decoder past an encoded value of type `T`, without decoding it, or allocating any memory.
+    /// See [`TrySkip`] for more information.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+    #[cfg_attr(feature = "alloc", doc = "```")]
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// // A sequence of 2 strings: `["a", "b"]`, followed by a bool.
+    /// let buffer: &[u8] = &[8, 4, b'a', 4, b'b', 1];
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+    ///
+    /// decoder.skip::<Vec<String>>().unwrap();
+    /// assert_eq!(decoder.try_decode::<bool>(), Ok(true));
+    /// ```
+    pub fn skip<T: TrySkip<E> + ?Sized>(&mut self) -> DecodeResult<()> {
+        T::try_skip(self)
+    }
+
+    /// Decodes the length of a collection (encoded as a size), and checks that it's plausible, given that each element
+    /// of the collection is encoded on at least `min_element_size` bytes.
+    ///
+    /// If `length * min_element_size` is greater than the number of bytes remaining in this decoder's buffer, the
+    /// collection can't possibly fit, so this returns `Err` immediately, instead of letting the caller allocate storage
+    /// for the collection, and loop through its elements until running out of bytes.
+    ///
+    /// Element types which are allowed to encode on zero bytes should pass a `min_element_size` of `0`,
+    /// which disables this check. See [`TryDecode::MIN_ENCODED_SIZE`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// // A length of '2', followed by 4 bytes.
+    /// let buffer: &[u8] = &[8, 1, 2, 3, 4];
+    ///
+    /// // 2 elements of 2 bytes each fit in the 4 remaining bytes.
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+    /// assert_eq!(decoder.try_decode_collection_length(2), Ok(2));
+    ///
+    /// // But 2 elements of 4 bytes each don't.
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+    /// assert!(decoder.try_decode_collection_length(4).is_err());
+    /// ```
+    pub fn try_decode_collection_length(&mut self, min_element_size: usize) -> DecodeResult<usize> {
+        let offset = self.position();
+        let length = E::try_decode_size(self)?;
+
+        // We saturate instead of overflowing, since no buffer can hold `usize::MAX` bytes anyways.
+        let minimum_size = length.saturating_mul(min_element_size);
+        let remaining = self.remaining();
+        if minimum_size > remaining {
+            // In incremental mode, the rest of the collection may just not have arrived yet.
+            if self.incremental {
+                return Err(self.end_of_buffer_error(minimum_size - remaining, self.position()));
+            }
+
+            let kind = DecodeErrorKind::InvalidData {
+                desc: "collection length is larger than the number of bytes remaining in the buffer",
+            };
+            return Err(DecodeError::new(kind, offset));
+        }
+        Ok(length)
+    }
+
+    /// Returns the number of bytes taken up by `length`-many elements which are each `element_size` bytes long.
+    ///
+    /// [`try_decode_collection_length`](Self::try_decode_collection_length) can't rule out overflow on its own, since
+    /// decoders which read from a stream don't know how many bytes are remaining. So if the number of bytes doesn't fit
+    /// in a `usize`, this returns [`InvalidData`](DecodeErrorKind::InvalidData) instead, reported at `offset`.
+    pub(crate) fn collection_byte_size(length: usize, element_size: usize, offset: usize) -> DecodeResult<usize> {
+        length.checked_mul(element_size).ok_or_else(|| {
+            let kind = DecodeErrorKind::InvalidData {
+                desc: "collection length is larger than the number of bytes that can be addressed",
+            };
+            DecodeError::new(kind, offset)
+        })
+    }
+
     /// Returns a reference to the next byte in this decoder's buffer, if present.
     /// This byte is not consumed, and the decoder's position is not advanced by calling this function.
     ///
@@ -117,8 +615,10 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// # Examples
     ///
     /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
     /// let buffer: &[u8] = &[7, 9];
-    /// let decoder: Decoder<Slice2> = Decoder::new(buffer);
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
     ///
     /// // `peek_byte` returns the same value as `read_byte`, but doesn't consume it.
     /// assert_eq!(decoder.peek_byte(), Some(&7));
@@ -135,7 +635,8 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// assert!(decoder.read_byte().is_err());
     /// ```
     pub fn peek_byte(&mut self) -> Option<&u8> {
-        todo!() // TODO
+        self.limit_input_storage();
+        self.input.peek_byte().ok()
     }
 
     /// Returns a reference to the next byte in this decoder's buffer, if present, then advances this decoder's position
@@ -148,8 +649,10 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// # Examples
     ///
     /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
     /// let buffer: &[u8] = &[1, 2, 8];
-    /// let decoder: Decoder<Slice2> = Decoder::new(buffer);
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
     ///
     /// // `read_byte` consumes and returns bytes from the decoder's buffer, one at a time.
     /// assert_eq!(decoder.read_byte(), Ok(&1));
@@ -160,7 +663,10 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// assert!(decoder.read_byte().is_err());
     /// ```
     pub fn read_byte(&mut self) -> DecodeResult<&'a u8> {
-        todo!() // TODO
+        self.limit_input_storage();
+        let result = self.input.read_byte();
+        self.charge_input_storage()?;
+        result.map_err(|error| self.input_error(error))
     }
 
     /// Returns a slice of the next `count`-many bytes in this decoder's buffer.
@@ -173,12 +679,14 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// # Examples
     ///
     /// ```
-    /// let buffer: &[u8] = &[1, 2, 3, 4, 5, 6];
-    /// let decoder: Decoder<Slice2> = Decoder::new(buffer);
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let buffer: &[u8] = &[1, 2, 3, 4, 5];
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
     ///
     /// // `peek_bytes` returns the same values as `read_bytes_exact`, but doesn't consume them.
     /// assert_eq!(decoder.peek_bytes(3), &[1, 2, 3]);
-    /// assert_eq!(decoder.read_bytes(3), Ok(&[1, 2, 3]));
+    /// assert_eq!(decoder.read_bytes_exact(3), Ok(&[1, 2, 3][..]));
     ///
     /// // Calling `peek_bytes` multiple times doesn't advance the decoder's position.
     /// assert_eq!(decoder.peek_bytes(2), &[4, 5]);
@@ -195,7 +703,8 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// assert_eq!(decoder.peek_bytes(5), &[]);
     /// ```
     pub fn peek_bytes(&mut self, count: usize) -> &[u8] {
-        todo!() // TODO
+        self.limit_input_storage();
+        self.input.peek_byte_slice(count)
     }
 
     /// Returns a slice of the next `count`-many bytes in this decoder's buffer, if all present, than advances this
@@ -207,24 +716,29 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// # Examples
     ///
     /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
     /// let buffer: &[u8] = &[1, 2, 3, 4, 5, 6];
-    /// let decoder: Decoder<Slice2> = Decoder::new(buffer);
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
     ///
     /// // `read_bytes_exact` consumes and returns exactly `count` bytes from the decoder's buffer.
-    /// assert_eq!(decoder.read_bytes_exact(2), Ok(&[1, 2]));
-    /// assert_eq!(decoder.read_bytes_exact(1), Ok(&[3]));
-    /// assert_eq!(decoder.read_bytes_exact(0), Ok(&[]));
-    /// assert_eq!(decoder.read_bytes_exact(1), Ok(&[4]));
+    /// assert_eq!(decoder.read_bytes_exact(2), Ok(&[1, 2][..]));
+    /// assert_eq!(decoder.read_bytes_exact(1), Ok(&[3][..]));
+    /// assert_eq!(decoder.read_bytes_exact(0), Ok(&[][..]));
+    /// assert_eq!(decoder.read_bytes_exact(1), Ok(&[4][..]));
     ///
     /// // `read_bytes_exact` returns an error if the you request more bytes than are available...
     /// assert!(decoder.read_bytes_exact(10).is_err());
     ///
     /// // ... but leaves the buffer unaffected, so any remaining bytes can still be retrieved.
-    /// assert_eq!(decoder.read_bytes_exact(2), Ok(&[5, 6]));
+    /// assert_eq!(decoder.read_bytes_exact(2), Ok(&[5, 6][..]));
     /// assert_eq!(decoder.remaining(), 0);
     /// ```
     pub fn read_bytes_exact(&mut self, count: usize) -> DecodeResult<&'a [u8]> {
-        todo!() // TODO
+        self.limit_input_storage();
+        let result = self.input.read_byte_slice_exact(count);
+        self.charge_input_storage()?;
+        result.map_err(|error| self.input_error(error))
     }
 
     /// Equivalent to [`read_bytes_exact`], but returns a reference to an array with a static length, instead of a slice
@@ -238,24 +752,29 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// # Examples
     ///
     /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
     /// let buffer: &[u8] = &[1, 2, 3, 4, 5, 6];
-    /// let decoder: Decoder<Slice2> = Decoder::new(buffer);
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
     ///
-    /// // `read_array_exact` consumes and returns exactly `count` bytes from the decoder's buffer.
-    /// assert_eq!(decoder.read_array_exact(2), Ok(&[1, 2]));
-    /// assert_eq!(decoder.read_array_exact(1), Ok(&[3]));
-    /// assert_eq!(decoder.read_array_exact(0), Ok(&[]));
-    /// assert_eq!(decoder.read_array_exact(1), Ok(&[4]));
+    /// // `read_array_exact` consumes and returns exactly `N` bytes from the decoder's buffer.
+    /// assert_eq!(decoder.read_array_exact::<2>(), Ok(&[1, 2]));
+    /// assert_eq!(decoder.read_array_exact::<1>(), Ok(&[3]));
+    /// assert_eq!(decoder.read_array_exact::<0>(), Ok(&[]));
+    /// assert_eq!(decoder.read_array_exact::<1>(), Ok(&[4]));
     ///
     /// // `read_array_exact` returns an error if the you request more bytes than are available...
-    /// assert!(decoder.read_array_exact(10).is_err());
+    /// assert!(decoder.read_array_exact::<10>().is_err());
     ///
     /// // ... but leaves the buffer unaffected, so any remaining bytes can still be retrieved.
-    /// assert_eq!(decoder.read_array_exact(2), Ok(&[5, 6]));
+    /// assert_eq!(decoder.read_array_exact::<2>(), Ok(&[5, 6]));
     /// assert_eq!(decoder.remaining(), 0);
     /// ```
     pub fn read_array_exact<const N: usize>(&mut self) -> DecodeResult<&'a [u8; N]> {
-        todo!() // TODO
+        self.limit_input_storage();
+        let result = self.input.read_bytes_exact::<N>();
+        self.charge_input_storage()?;
+        result.map_err(|error| self.input_error(error))
     }
 
     /// Returns the number of bytes remaining in this decoder's buffer.
@@ -266,16 +785,83 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// # Examples
     ///
     /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
     /// let buffer: &[u8] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
-    /// let decoder: Decoder<Slice2> = Decoder::new(buffer);
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
     ///
     /// assert_eq!(decoder.remaining(), 10);
     ///
-    /// assert_eq!(decoder.read_bytes_exact(6), Ok(&[0, 1, 2, 3, 4, 5]));
+    /// assert_eq!(decoder.read_bytes_exact(6), Ok(&[0, 1, 2, 3, 4, 5][..]));
     /// assert_eq!(decoder.remaining(), 4);
     /// ```
     pub fn remaining(&mut self) -> usize {
-        todo!() // TODO
+        self.input.remaining()
+    }
+
+    /// Returns the [`Bytes`](bytes::Bytes) this decoder is reading from, if it was created with
+    /// [`from_bytes`](Self::from_bytes).
+    #[cfg(feature = "bytes")]
+    pub(crate) fn input_bytes(&self) -> Option<&'a bytes::Bytes> {
+        self.input.source_bytes()
+    }
+
+    /// Limits how much memory this decoder's input can allocate to the space left under this decoder's allocation
+    /// limit, so that inputs which read from a stream fail before allocating memory they couldn't be charged for.
+    /// This is a no-op for inputs that aren't filled over time.
+    ///
+    /// This is called before every read or peek, since the space left changes as the decoder allocates.
+    fn limit_input_storage(&mut self) {
+        #[cfg(feature = "std")]
+        {
+            // Bytes which were stored but not yet charged still count against the space that's left.
+            let limit = self.charged_input_storage.saturating_add(self.remaining_heap_allocation_size());
+            self.input.set_storage_limit(limit);
+        }
+    }
+
+    /// Charges any memory this decoder's input has allocated since the last call against this decoder's allocation
+    /// limit. This is a no-op for inputs that aren't filled over time, since they don't allocate anything.
+    ///
+    /// This is called after every read. Bytes that are only peeked at are charged by the next read instead, since
+    /// peeking can't report errors.
+    fn charge_input_storage(&mut self) -> DecodeResult<()> {
+        #[cfg(feature = "std")]
+        {
+            let stored = self.input.stored();
+            if stored > self.charged_input_storage {
+                self.increase_heap_allocation_total(stored - self.charged_input_storage)?;
+                self.charged_input_storage = stored;
+            }
+        }
+        Ok(())
+    }
+
+    /// Converts an error returned by this decoder's input into a [`DecodeError`] at the decoder's current position.
+    fn input_error(&self, error: io_types::Error) -> DecodeError {
+        let position = self.position();
+        match error {
+            io_types::Error::UnexpectedEof => self.end_of_buffer_error(1, position),
+            io_types::Error::InsufficientSpace { requested, remaining } => {
+                self.end_of_buffer_error(requested - remaining, position)
+            }
+            #[cfg(feature = "std")]
+            error @ (io_types::Error::Io(_) | io_types::Error::StorageLimitReached { .. }) => {
+                DecodeError::new(error.into(), position)
+            }
+        }
+    }
+
+    /// Returns the error this decoder reports when it needs `needed`-many more bytes than its input has, at `offset`.
+    ///
+    /// This is [`Incomplete`](DecodeErrorKind::Incomplete) in incremental mode, and
+    /// [`EndOfBuffer`](DecodeErrorKind::EndOfBuffer) otherwise.
+    pub(crate) fn end_of_buffer_error(&self, needed: usize, offset: usize) -> DecodeError {
+        let kind = match self.incremental {
+            true => DecodeErrorKind::Incomplete { needed },
+            false => DecodeErrorKind::EndOfBuffer,
+        };
+        DecodeError::new(kind, offset)
     }
 
     /// Returns the amount of heap memory (in bytes) this decoder can allocate before hitting its allocation limit.
@@ -289,23 +875,20 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// # Examples
     ///
     /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
     /// // Create a decoder over an empty buffer, with an allocation limit of '16' bytes.
-    /// let decoder: Decoder<Slice2> = Decoder::new(&[]).with_allocation_limit(16);
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(&[]).with_allocation_limit(16);
     ///
-    /// assert_eq!(decoder,remaining_heap_allocation_size(), 16);
+    /// assert_eq!(decoder.remaining_heap_allocation_size(), 16);
     ///
     /// // Pretend we're about to allocate 10 bytes of heap memory.
     /// assert!(decoder.increase_heap_allocation_total(10).is_ok());
-    /// assert_eq!(decoder,remaining_heap_allocation_size(), 6);
+    /// assert_eq!(decoder.remaining_heap_allocation_size(), 6);
     /// ```
     #[cfg(feature = "alloc")]
     pub fn remaining_heap_allocation_size(&self) -> usize {
-        if self.maximum_allowed_heap_allocation_size == NO_LIMIT {
-            NO_LIMIT
-        } else {
-            // SAFETY: overflow is impossible because `total_heap_allocation_size <= maximum_allowed_heap_allocation_size`.
-            self.maximum_allowed_heap_allocation_size - self.total_heap_allocation_size
-        }
+        self.allocation_tracker.get().remaining()
     }
 
     /// Any decoding logic that is about to allocate heap memory must first call this function before allocating,
@@ -325,8 +908,10 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// # Examples
     ///
     /// ```
+    /// # use slice_encoding::decoder::{Decoder, DecodeResult};
+    /// # use slice_encoding::slice2::Slice2;
     /// // Mock function that attempts to allocate `capacity`-many bytes of heap memory.
-    /// let allocate_vec = |capacity| {
+    /// fn allocate_vec(decoder: &mut Decoder<Slice2>, capacity: usize) -> DecodeResult<Vec<u8>> {
     ///     // Ensure we can allocate `capacity` many bytes without passing the decoder's limit.
     ///     decoder.increase_heap_allocation_total(capacity)?;
     ///     // If the call didn't return `Err`, it's safe to allocate.
@@ -334,33 +919,128 @@ impl<'a, E: Encoding> Decoder<'a, E> {
     /// }
     ///
     /// // Create a decoder over an empty buffer, with an allocation limit of '16' bytes.
-    /// let decoder: Decoder<Slice2> = Decoder::new(&[]).with_allocation_limit(16);
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(&[]).with_allocation_limit(16);
     ///
     /// // The first time we call `allocate_vec`, `10 < 16` so the allocation succeeds.
-    /// assert!(allocate_vec(10).is_ok());
+    /// assert!(allocate_vec(&mut decoder, 10).is_ok());
     ///
     /// // The second time we call it, `20 > 16`, so the allocation fails.
-    /// assert!(allocate_vec(10).is_err());
+    /// assert!(allocate_vec(&mut decoder, 10).is_err());
     ///
     /// // It's still safe to perform allocations after an err, if they are within the limit.
-    /// assert!(allocate_vec(6).is_ok());
+    /// assert!(allocate_vec(&mut decoder, 6).is_ok());
     /// ```
     #[cfg(feature = "alloc")]
     pub fn increase_heap_allocation_total(&mut self, size: usize) -> DecodeResult<()> {
-        let new_total_heap_allocation_size = self.total_heap_allocation_size.saturating_add(size);
-
-        // If the new total allocation size is within the limit, update the total allocation size and return `Ok`,
-        // otherwise return a (`HeapAllocationLimitReached`)[`DecodeError::HeapAllocationLimitReached`] error.
-        if new_total_heap_allocation_size <= self.maximum_allowed_heap_allocation_size {
-            self.total_heap_allocation_size = new_total_heap_allocation_size;
-            Ok(())
-        } else {
-            Err(DecodeError::HeapAllocationLimitReached {
-                limit: self.maximum_allowed_heap_allocation_size,
-                current: self.total_heap_allocation_size,
-                requested: size,
-            })
+        // If the new total allocation size is within the limit, the tracker updates its total and returns `Ok`,
+        // otherwise it returns a (`HeapAllocationLimitReached`)[`DecodeErrorKind::HeapAllocationLimitReached`] error.
+        self.allocation_tracker
+            .get()
+            .try_increase_total(size)
+            .map_err(|kind| DecodeError::new(kind, self.position()))
+    }
+
+    /// Calls [`increase_heap_allocation_total`](Self::increase_heap_allocation_total) with the amount of heap memory
+    /// needed to store `count`-many values of type `T`: `count * size_of::<T>()`. If this multiplication overflows,
+    /// the result saturates at [`usize::MAX`], which is guaranteed to exceed any allocation limit other than none.
+    ///
+    /// Any decoding logic that is about to allocate storage for a collection must call this function first, since the
+    /// size of a collection is usually read from the payload, and can't be trusted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// // Create a decoder over an empty buffer, with an allocation limit of '16' bytes.
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(&[]).with_allocation_limit(16);
+    ///
+    /// // 3 `i32`s take up 12 bytes of memory, which is within the limit.
+    /// assert!(decoder.increase_heap_allocation_total_for::<i32>(3).is_ok());
+    /// assert_eq!(decoder.remaining_heap_allocation_size(), 4);
+    ///
+    /// // But 2 more would take up 8 bytes, which isn't.
+    /// assert!(decoder.increase_heap_allocation_total_for::<i32>(2).is_err());
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn increase_heap_allocation_total_for<T>(&mut self, count: usize) -> DecodeResult<()> {
+        // The default tracker's limit depends on the size of the largest element, so it needs to know about each one.
+        if let DecoderAllocationTracker::Default(tracker) = &self.allocation_tracker {
+            tracker.record_element_size(core::mem::size_of::<T>());
+        }
+        self.increase_heap_allocation_total(count.saturating_mul(core::mem::size_of::<T>()))
+    }
+
+    /// Any decoding logic that is about to decode a nested value (one which can contain other values, like a sequence
+    /// or a struct) must first call this function, and then call [`exit`](Self::exit) once it's finished.
+    ///
+    /// The decoder keeps track of how deeply nested the value it's currently decoding is, and has a limit on this
+    /// depth. Normally, this function just increases the current depth and returns `Ok(())`, but if this would put the
+    /// decoder over its limit, it returns a [`DepthLimitExceeded`](DecodeErrorKind::DepthLimitExceeded) error instead.
+    ///
+    /// This logic protects against malformed or malicious payloads overflowing the stack with deeply nested values.
+    /// Most callers should use [`nested`](Self::nested) instead, which calls `enter` and `exit` for them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(&[]).with_max_depth(1);
+    ///
+    /// assert!(decoder.enter().is_ok());
+    /// assert!(decoder.enter().is_err());
+    /// decoder.exit();
+    /// assert!(decoder.enter().is_ok());
+    /// ```
+    pub fn enter(&mut self) -> DecodeResult<()> {
+        if self.depth >= self.max_depth {
+            let kind = DecodeErrorKind::DepthLimitExceeded { limit: self.max_depth };
+            return Err(DecodeError::new(kind, self.position()));
         }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Marks the end of a nested value, which was started by calling [`enter`](Self::enter).
+    pub fn exit(&mut self) {
+        debug_assert!(self.depth > 0, "called 'exit' without a matching call to 'enter'");
+        self.depth = self.depth.saturating_sub(1);
+    }
+
+    /// Calls the provided function between calls to [`enter`](Self::enter) and [`exit`](Self::exit), and returns its
+    /// result. `exit` is called whether the function succeeds or not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::decoder::{Decoder, DecodeResult};
+    /// # use slice_encoding::slice2::Slice2;
+    /// // A recursive type: each node can hold another node.
+    /// struct Node {
+    ///     child: Option<Box<Node>>,
+    /// }
+    ///
+    /// fn decode_node(decoder: &mut Decoder<Slice2>) -> DecodeResult<Node> {
+    ///     decoder.nested(|decoder| {
+    ///         let child = match decoder.try_decode::<bool>()? {
+    ///             true => Some(Box::new(decode_node(decoder)?)),
+    ///             false => None,
+    ///         };
+    ///         Ok(Node { child })
+    ///     })
+    /// }
+    ///
+    /// // A hostile payload with a million nested nodes fails cleanly, instead of overflowing the stack.
+    /// let buffer = vec![1; 1_000_000];
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(&buffer);
+    /// assert!(decode_node(&mut decoder).is_err());
+    /// ```
+    pub fn nested<T>(&mut self, decode_fn: impl FnOnce(&mut Self) -> DecodeResult<T>) -> DecodeResult<T> {
+        self.enter()?;
+        let result = decode_fn(self);
+        self.exit();
+        result
     }
 }
 
@@ -369,51 +1049,432 @@ impl<'a, E: Encoding> Decoder<'a, E> {
 /// It is a direct mapping to [`std::result::Result`] with an `Err` type of [`DecodeError`].
 pub type DecodeResult<T> = Result<T, DecodeError>;
 
-/// TODO
-#[derive(Debug)]
-pub enum DecodeError {
-    /// TODO
+/// The error type for decoding operations.
+///
+/// Each error records what went wrong (its [`kind`](Self::kind)), and the [`offset`](Self::offset) into the decoder's
+/// input where it happened. If the 'alloc' feature is set, errors also carry a breadcrumb [`path`](Self::path) that
+/// describes which field or type was being decoded. This path is built up as the error propagates outwards through
+/// nested `try_decode` calls, using the functions provided by [`DecodeContext`].
+///
+/// # Examples
+///
+#[cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+#[cfg_attr(feature = "alloc", doc = "```")]
+/// # use slice_encoding::decoder::{Decoder, DecodeContext, DecodeErrorKind, DecodeResult};
+/// # use slice_encoding::slice2::Slice2;
+/// #[derive(Debug)]
+/// struct Item {
+///     in_stock: bool,
+/// }
+///
+/// fn decode_item(decoder: &mut Decoder<Slice2>) -> DecodeResult<Item> {
+///     let in_stock = decoder.try_decode().in_field("in_stock")?;
+///     Ok(Item { in_stock })
+/// }
+///
+/// // `2` isn't a valid bool, so decoding the item fails.
+/// let mut decoder: Decoder<Slice2> = Decoder::new(&[1, 2]);
+/// decoder.read_byte().unwrap();
+/// let error = decode_item(&mut decoder).in_type("Item").unwrap_err();
+///
+/// assert!(matches!(error.kind(), DecodeErrorKind::IllegalValue { value: 2, .. }));
+/// assert_eq!(error.offset(), 1);
+/// assert_eq!(
+///     error.to_string(),
+///     "illegal value '2' at offset 1 (while decoding 'Item.in_stock'): bools can only have a numeric value of `0` or `1`",
+/// );
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    /// What went wrong.
+    kind: DecodeErrorKind,
+
+    /// The offset (in bytes) into the decoder's input where the error occurred.
+    offset: usize,
+
+    /// Which fields and types were being decoded when the error occurred, ordered from innermost to outermost, since
+    /// that's the order segments are added in. See [`Self::with_segment`].
+    #[cfg(feature = "alloc")]
+    path: Vec<PathSegment>,
+}
+
+impl DecodeError {
+    /// Creates a new error of the specified kind, which occurred at the specified offset into the decoder's input.
+    pub fn new(kind: DecodeErrorKind, offset: usize) -> Self {
+        DecodeError {
+            kind,
+            offset,
+            #[cfg(feature = "alloc")]
+            path: Vec::new(),
+        }
+    }
+
+    /// Returns what kind of error this is.
+    pub fn kind(&self) -> &DecodeErrorKind {
+        &self.kind
+    }
+
+    /// Returns the offset (in bytes) into the decoder's input where this error occurred.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the breadcrumb path describing which fields and types were being decoded when this error occurred,
+    /// ordered from outermost to innermost. This is empty if no context was attached to this error.
+    #[cfg(feature = "alloc")]
+    pub fn path(&self) -> Rev<slice::Iter<'_, PathSegment>> {
+        self.path.iter().rev()
+    }
+
+    /// Records that this error occurred while decoding the field with the provided name.
+    pub fn in_field(self, name: &'static str) -> Self {
+        self.with_segment(PathSegment::Field(name))
+    }
+
+    /// Records that this error occurred while decoding the element at the provided index of a sequence.
+    pub fn at_index(self, index: usize) -> Self {
+        self.with_segment(PathSegment::Index(index))
+    }
+
+    /// Records that this error occurred while decoding an instance of the type with the provided name.
+    pub fn in_type(self, typename: &'static str) -> Self {
+        self.with_segment(PathSegment::Type(typename))
+    }
+
+    /// Adds the provided segment to the front of this error's path.
+    /// Since errors propagate outwards, each new segment is outside of all the segments added before it.
+    ///
+    /// The path is stored from innermost to outermost, so this only has to push the segment onto the end of it.
+    #[cfg_attr(not(feature = "alloc"), allow(unused_mut, unused_variables))]
+    fn with_segment(mut self, segment: PathSegment) -> Self {
+        #[cfg(feature = "alloc")]
+        self.path.push(segment);
+        self
+    }
+}
+
+impl core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        // Write the first half of the message, which describes the error and where it occurred.
+        match &self.kind {
+            DecodeErrorKind::InvalidData { .. } => write!(f, "invalid data")?,
+            DecodeErrorKind::IllegalValue { value, .. } => write!(f, "illegal value '{value}'")?,
+            DecodeErrorKind::OutOfRange { value, typename, .. } => {
+                write!(f, "value '{value}' is out of range for '{typename}'")?
+            }
+            DecodeErrorKind::HeapAllocationLimitReached { .. } => write!(f, "heap allocation limit reached")?,
+            DecodeErrorKind::DepthLimitExceeded { .. } => write!(f, "depth limit exceeded")?,
+            DecodeErrorKind::EndOfBuffer => write!(f, "unexpectedly reached end-of-buffer")?,
+            DecodeErrorKind::Incomplete { .. } => write!(f, "incomplete input")?,
+            #[cfg(feature = "std")]
+            DecodeErrorKind::Io { .. } => write!(f, "failed to read input")?,
+        }
+        write!(f, " at offset {}", self.offset)?;
+
+        #[cfg(feature = "alloc")]
+        if !self.path.is_empty() {
+            write!(f, " (while decoding '")?;
+            for (i, segment) in self.path().enumerate() {
+                // Fields are separated from whatever comes before them with a '.'.
+                if i > 0 && matches!(segment, PathSegment::Field(_)) {
+                    write!(f, ".")?;
+                }
+                write!(f, "{segment}")?;
+            }
+            write!(f, "')")?;
+        }
+
+        // Write the second half of the message, which gives more details about the error, if there are any.
+        match &self.kind {
+            DecodeErrorKind::InvalidData { desc } | DecodeErrorKind::IllegalValue { desc, .. } => write!(f, ": {desc}"),
+            DecodeErrorKind::OutOfRange { min, max, .. } => {
+                write!(f, ": must be between '{min}' and '{max}' (inclusive)")
+            }
+            DecodeErrorKind::HeapAllocationLimitReached { limit, current, requested } => write!(
+                f,
+                ": attempted to allocate {requested} bytes, but {current} of the {limit} allowed bytes were already used",
+            ),
+            DecodeErrorKind::DepthLimitExceeded { limit } => {
+                write!(f, ": values cannot be nested more than {limit} levels deep")
+            }
+            DecodeErrorKind::EndOfBuffer => Ok(()),
+            DecodeErrorKind::Incomplete { needed } => write!(f, ": at least {needed} more bytes are needed"),
+            #[cfg(feature = "std")]
+            DecodeErrorKind::Io { kind } => write!(f, ": {kind}"),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+/// Describes what went wrong while decoding. See [`DecodeError::kind`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    /// The encoded data was malformed, and doesn't conform to the Slice encoding.
     InvalidData {
-        /// TODO
+        /// A description of what was invalid about the data.
         desc: &'static str
     },
 
-    /// TODO
+    /// A value was decoded which isn't legal for the type being decoded. For example, a bool with a value of `2`.
     IllegalValue {
-        /// TODO
+        /// The value that was decoded.
         value: i128,
-        /// TODO
+        /// A description of why the value is illegal.
         desc: &'static str,
     },
 
-    /// TODO
+    /// A value was decoded which is outside the range of values that the type being decoded can hold.
     OutOfRange {
-        /// TODO
+        /// The value that was decoded.
         value: i128,
-        /// TODO
+        /// The minimum value that the type can hold.
         min: i128,
-        /// TODO
+        /// The maximum value that the type can hold.
         max: i128,
-        /// TODO
+        /// The name of the Slice type that was being decoded.
         typename: &'static str,
     },
 
-    /// TODO
+    /// Decoding would have required allocating more heap memory than the decoder's allocation limit allows.
+    /// See [`Decoder::increase_heap_allocation_total`] for more information.
     HeapAllocationLimitReached {
-        /// TODO
+        /// The decoder's allocation limit.
         limit: usize,
-        /// TODO
+        /// The amount of heap memory the decoder had already allocated.
         current: usize,
-        /// TODO
+        /// The amount of heap memory that was requested.
         requested: usize,
     },
 
-    /// TODO
-    // TODO this should take some data or something.
+    /// Decoding would have required nesting values more deeply than the decoder's depth limit allows.
+    /// See [`Decoder::enter`] for more information.
+    DepthLimitExceeded {
+        /// The decoder's depth limit.
+        limit: usize,
+    },
+
+    /// The decoder reached the end of its input before it finished decoding a value.
     EndOfBuffer,
+
+    /// An incremental decoder reached the end of its input before it finished decoding a value, but more bytes may
+    /// still arrive. See [`Decoder::with_incremental_mode`] for more information.
+    Incomplete {
+        /// The minimum number of additional bytes needed to make progress. More may be needed after they arrive.
+        needed: usize,
+    },
+
+    /// Reading more bytes from the decoder's input failed. This can only happen for inputs which read from a stream.
+    /// See [`Decoder::from_reader`] for more information.
+    #[cfg(feature = "std")]
+    Io {
+        /// The kind of I/O error that occurred.
+        kind: std::io::ErrorKind,
+    },
+}
+
+/// A saved position in a decoder's input, which decoding can later be resumed from.
+/// See [`Decoder::checkpoint`] and [`Decoder::resume_from`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    position: usize,
+}
+
+impl Checkpoint {
+    /// Returns the position (in bytes) into the decoder's input that this checkpoint was taken at.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+/// A single segment of a [`DecodeError`]'s breadcrumb path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathSegment {
+    /// A field with the provided name.
+    Field(&'static str),
+    /// An element of a sequence, at the provided index.
+    Index(usize),
+    /// An instance of the type with the provided name.
+    Type(&'static str),
+}
+
+impl core::fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PathSegment::Field(name) => write!(f, "{name}"),
+            PathSegment::Index(index) => write!(f, "[{index}]"),
+            PathSegment::Type(typename) => write!(f, "{typename}"),
+        }
+    }
+}
+
+/// Extension functions for attaching breadcrumb context to the errors held by [`DecodeResult`]s.
+///
+/// Each of these functions leaves `Ok` values untouched, and delegates to the function of the same name on
+/// [`DecodeError`] for `Err` values.
+pub trait DecodeContext {
+    /// Records that the error occurred while decoding the field with the provided name.
+    fn in_field(self, name: &'static str) -> Self;
+
+    /// Records that the error occurred while decoding the element at the provided index of a sequence.
+    fn at_index(self, index: usize) -> Self;
+
+    /// Records that the error occurred while decoding an instance of the type with the provided name.
+    fn in_type(self, typename: &'static str) -> Self;
+}
+
+impl<T> DecodeContext for DecodeResult<T> {
+    fn in_field(self, name: &'static str) -> Self {
+        self.map_err(|error| error.in_field(name))
+    }
+
+    fn at_index(self, index: usize) -> Self {
+        self.map_err(|error| error.at_index(index))
+    }
+
+    fn in_type(self, typename: &'static str) -> Self {
+        self.map_err(|error| error.in_type(typename))
+    }
+}
+
+impl<'a, E: Encoding> From<BufferedInput<'a>> for Decoder<'a, E> {
+    /// Creates a new decoder that reads bytes from the provided input.
+    ///
+    /// The decoder's allocation limit is initially set to [`DEFAULT_ALLOCATION_LIMIT_FACTOR`] times the number of bytes
+    /// remaining in `input`, or [`DEFAULT_STREAMING_ALLOCATION_LIMIT`] if `input` reads from a stream. See
+    /// [default allocation limits](Decoder#default-allocation-limits).
+    fn from(input: BufferedInput<'a>) -> Self {
+        #[cfg(feature = "alloc")]
+        let allocation_tracker = {
+            let element_limit = DEFAULT_ALLOCATION_LIMIT_FACTOR.saturating_mul(input.remaining());
+            DecoderAllocationTracker::Default(ElementAllocationTracker::new(element_limit))
+        };
+        #[cfg(feature = "std")]
+        let allocation_tracker = match input.is_streaming() {
+            true => DecoderAllocationTracker::Owned(LocalAllocationTracker::new(DEFAULT_STREAMING_ALLOCATION_LIMIT)),
+            false => allocation_tracker,
+        };
+
+        Decoder {
+            encoding: E::default(),
+            input,
+            strict: false,
+            incremental: false,
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            #[cfg(feature = "alloc")]
+            allocation_tracker,
+            #[cfg(feature = "std")]
+            charged_input_storage: 0,
+        }
+    }
+}
+
+impl From<io_types::Error> for DecodeErrorKind {
+    fn from(error: io_types::Error) -> Self {
+        match error {
+            io_types::Error::UnexpectedEof | io_types::Error::InsufficientSpace { .. } => DecodeErrorKind::EndOfBuffer,
+            #[cfg(feature = "std")]
+            io_types::Error::Io(kind) => DecodeErrorKind::Io { kind },
+            #[cfg(feature = "std")]
+            io_types::Error::StorageLimitReached { limit, stored, requested } => {
+                DecodeErrorKind::HeapAllocationLimitReached { limit, current: stored, requested }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    // TODO
+    use super::*;
+
+    #[cfg(feature = "alloc")]
+    use alloc::string::ToString;
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn errors_without_context_only_describe_their_kind_and_offset() {
+        let error = DecodeError::new(DecodeErrorKind::EndOfBuffer, 7);
+
+        assert_eq!(error.to_string(), "unexpectedly reached end-of-buffer at offset 7");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn path_segments_are_ordered_from_outermost_to_innermost() {
+        let kind = DecodeErrorKind::InvalidData { desc: "bad price" };
+        let result: DecodeResult<()> = Err(DecodeError::new(kind, 12));
+
+        // Context is attached as the error propagates outwards, so the innermost segment is attached first.
+        let error = result.in_field("price").at_index(3).in_field("items").in_type("Order").unwrap_err();
+
+        let expected = [
+            PathSegment::Type("Order"),
+            PathSegment::Field("items"),
+            PathSegment::Index(3),
+            PathSegment::Field("price"),
+        ];
+        assert!(error.path().eq(&expected));
+        assert_eq!(error.to_string(), "invalid data at offset 12 (while decoding 'Order.items[3].price'): bad price");
+    }
+
+    #[cfg(all(feature = "alloc", feature = "slice2"))]
+    #[test]
+    fn incremental_decoders_report_every_truncated_payload_as_incomplete() {
+        use crate::slice2::Slice2;
+        use alloc::string::String;
+        use alloc::vec;
+
+        // A sequence of 2 strings: `["ab", "c"]`, followed by an `i16`.
+        let payload: &[u8] = &[8, 8, b'a', b'b', 4, b'c', 3, 0];
+
+        for end in 0..payload.len() {
+            let input = &payload[..end];
+            let mut decoder: Decoder<Slice2> = Decoder::new(input).with_incremental_mode();
+            let result = decoder.try_decode::<Vec<String>>().and_then(|_| decoder.try_decode::<i16>());
+
+            let error = result.unwrap_err();
+            assert!(matches!(error.kind(), DecodeErrorKind::Incomplete { needed } if *needed > 0), "{error}");
+        }
+
+        let mut decoder: Decoder<Slice2> = Decoder::new(payload).with_incremental_mode();
+        assert_eq!(decoder.try_decode::<Vec<String>>(), Ok(vec!["ab".into(), "c".into()]));
+        assert_eq!(decoder.try_decode::<i16>(), Ok(3));
+    }
+
+    #[test]
+    fn context_functions_do_not_affect_ok_results() {
+        let result: DecodeResult<u8> = Ok(4);
+
+        assert_eq!(result.in_field("field").at_index(0).in_type("Type"), Ok(4));
+    }
+
+    #[cfg(all(feature = "alloc", feature = "slice2"))]
+    #[test]
+    fn decoders_over_buffers_default_to_a_proportional_allocation_limit() {
+        use crate::slice2::Slice2;
+
+        let buffer = [0; 16];
+        let decoder: Decoder<Slice2> = Decoder::new(&buffer);
+        assert_eq!(decoder.remaining_heap_allocation_size(), DEFAULT_ALLOCATION_LIMIT_FACTOR * 16);
+
+        let decoder: Decoder<Slice2> = Decoder::new(&buffer).with_proportional_allocation_limit(2);
+        assert_eq!(decoder.remaining_heap_allocation_size(), 32);
+    }
+
+    #[cfg(all(feature = "std", feature = "slice2"))]
+    #[test]
+    fn decoders_over_streams_default_to_a_fixed_allocation_limit() {
+        use crate::io_types::reader::ReadBuffer;
+        use crate::slice2::Slice2;
+
+        let buffer = ReadBuffer::new();
+        let decoder: Decoder<Slice2> = Decoder::from_reader(&[0; 16][..], &buffer);
+        assert_eq!(decoder.remaining_heap_allocation_size(), DEFAULT_STREAMING_ALLOCATION_LIMIT);
+
+        // Streams don't know how many bytes they hold, so a proportional limit falls back to the same fixed limit.
+        let buffer = ReadBuffer::new();
+        let decoder: Decoder<Slice2> = Decoder::from_reader(&[0; 16][..], &buffer).with_proportional_allocation_limit(2);
+        assert_eq!(decoder.remaining_heap_allocation_size(), DEFAULT_STREAMING_ALLOCATION_LIMIT);
+    }
 }
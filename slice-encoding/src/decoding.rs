@@ -1,51 +1,102 @@
 // Copyright (c) ZeroC, Inc.
 
-use crate::decoder::{DecodeError, DecodeResult, Decoder};
+use crate::decoder::{DecodeError, DecodeErrorKind, DecodeResult, Decoder};
 use crate::Encoding;
-use crate::try_decode::TryDecode;
+use crate::try_decode::{TryDecode, TryDecodeBorrowed};
+
+#[cfg(feature = "alloc")]
+use crate::decoder::DecodeContext;
+#[cfg(feature = "alloc")]
+use crate::try_decode::{DecodeFn, TryDecodeCollection};
 
 // TODO ADD COMMENTS TO THIS ENTIRE FILE! WE'RE JUST GETTING IT READY FOR JOE.
 
+#[cfg(feature = "alloc")]
+use alloc::borrow::Cow;
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
 #[cfg(feature = "alloc")]
 use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
+
+#[cfg(feature = "std")]
+use core::hash::Hash;
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
 
 // =============================================================================
 // Fixed-length type implementations
 // =============================================================================
 
 impl<E: Encoding> TryDecode<E> for bool {
+    const MIN_ENCODED_SIZE: usize = 1;
+
     fn try_decode(decoder: &mut Decoder<E>) -> DecodeResult<Self> {
+        let offset = decoder.position();
         let byte = *decoder.read_byte()?;
 
         // We strictly enforce the Slice spec; A bool must be encoded as either `0` or `1`.
         match byte {
             0 | 1 => Ok(byte != 0),
-            _ => Err(DecodeError::IllegalValue {
-                value: byte as i128,
-                desc: "bools can only have a numeric value of `0` or `1`",
-            })
+            _ => {
+                let kind = DecodeErrorKind::IllegalValue {
+                    value: byte as i128,
+                    desc: "bools can only have a numeric value of `0` or `1`",
+                };
+                Err(DecodeError::new(kind, offset))
+            }
         }
     }
 }
 
 impl<E: Encoding> TryDecode<E> for u8 {
+    const MIN_ENCODED_SIZE: usize = 1;
+
     fn try_decode(decoder: &mut Decoder<E>) -> DecodeResult<Self> {
         decoder.read_byte().copied()
     }
+
+    /// Copies all the bytes out of the decoder at once.
+    #[cfg(feature = "alloc")]
+    fn try_decode_elements(decoder: &mut Decoder<E>, length: usize) -> DecodeResult<Vec<Self>> {
+        decoder.read_bytes_exact(length).map(<[u8]>::to_vec)
+    }
 }
 
 macro_rules! implement_slice_decodable_for_primitive_numeric_type {
     ($ty:ty, $doc_text:literal, $encoding:ident$(: $($bounds:tt)+)?) => {
         impl$(<$encoding: $($bounds)+>)? TryDecode<$encoding> for $ty {
+            const MIN_ENCODED_SIZE: usize = core::mem::size_of::<$ty>();
+
             #[doc = $doc_text]
             fn try_decode(decoder: &mut Decoder<$encoding>) -> DecodeResult<Self> {
                 let bytes = decoder.read_array_exact()?;
                 Ok(<$ty>::from_le_bytes(*bytes))
             }
+
+            /// Reads all the elements' bytes at once, then converts them in fixed-size chunks.
+            /// On little endian hosts, this conversion compiles down to a single copy.
+            #[cfg(feature = "alloc")]
+            fn try_decode_elements(decoder: &mut Decoder<$encoding>, length: usize) -> DecodeResult<alloc::vec::Vec<Self>> {
+                const SIZE: usize = core::mem::size_of::<$ty>();
+
+                // If this multiplication saturates, reading the bytes will fail, since no buffer is that large.
+                let bytes = decoder.read_bytes_exact(length.saturating_mul(SIZE))?;
+                // SAFETY: unwrapping is safe because `chunks_exact` guarantees each chunk is exactly `SIZE` bytes long.
+                let chunks = bytes.chunks_exact(SIZE);
+                Ok(chunks.map(|chunk| <$ty>::from_le_bytes(chunk.try_into().unwrap())).collect())
+            }
         }
     }
 }
 
+#[cfg(feature = "slice2")]
 pub(crate) use implement_slice_decodable_for_primitive_numeric_type;
 
 implement_slice_decodable_for_primitive_numeric_type!(i16, "TODO", E: Encoding);
@@ -60,12 +111,418 @@ implement_slice_decodable_for_primitive_numeric_type!(f64, "TODO", E: Encoding);
 
 #[cfg(feature = "alloc")]
 impl<E: Encoding> TryDecode<E> for String {
+    const MIN_ENCODED_SIZE: usize = 1;
+
+    fn try_decode(decoder: &mut Decoder<E>) -> DecodeResult<Self> {
+        // We decode a borrowed string first, so the input is validated as UTF-8 before we allocate anything.
+        let string = <&str>::try_decode_borrowed(decoder)?;
+        decoder.increase_heap_allocation_total_for::<u8>(string.len())?;
+        Ok(String::from(string))
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<E: Encoding> TryDecode<E> for Bytes {
+    const MIN_ENCODED_SIZE: usize = 1;
+
+    /// Decodes a sequence of bytes. If the decoder is reading from a [`Bytes`] (see
+    /// [`Decoder::from_bytes`]), this returns a reference-counted slice of it, without copying or allocating anything.
+    /// Otherwise, the bytes are copied into a new buffer.
+    fn try_decode(decoder: &mut Decoder<E>) -> DecodeResult<Self> {
+        let bytes = <&[u8]>::try_decode_borrowed(decoder)?;
+        match decoder.input_bytes() {
+            Some(source) => Ok(source.slice_ref(bytes)),
+            None => {
+                decoder.increase_heap_allocation_total_for::<u8>(bytes.len())?;
+                Ok(Bytes::copy_from_slice(bytes))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<E: Encoding, T> TryDecodeCollection<E, T> for Vec<T> {
+    fn try_decode_with_fn(decoder: &mut Decoder<E>, decode_fn: DecodeFn<T, E>) -> DecodeResult<Self> {
+        decoder.nested(|decoder| {
+            // We can't know how many bytes `decode_fn` reads, so we can't assume the elements take up any space.
+            let length = decoder.try_decode_collection_length(0)?;
+            decoder.increase_heap_allocation_total_for::<T>(length)?;
+            let mut vector = Vec::with_capacity(length);
+
+            for i in 0..length {
+                let element = decode_fn(decoder).at_index(i)?;
+                vector.push(element);
+            }
+            Ok(vector)
+        })
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<E: Encoding, T> TryDecode<E> for Vec<T>
+    where T: TryDecode<E>,
+{
+    const MIN_ENCODED_SIZE: usize = 1;
+
+    fn try_decode(decoder: &mut Decoder<E>) -> DecodeResult<Self> {
+        decoder.nested(|decoder| {
+            let length = decoder.try_decode_collection_length(T::MIN_ENCODED_SIZE)?;
+            decoder.increase_heap_allocation_total_for::<T>(length)?;
+            T::try_decode_elements(decoder, length)
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Encoding, T> TryDecodeCollection<E, T> for HashSet<T>
+    where T: Eq + Hash,
+{
+    fn try_decode_with_fn(decoder: &mut Decoder<E>, decode_fn: DecodeFn<T, E>) -> DecodeResult<Self> {
+        // We can't know how many bytes `decode_fn` reads, so we can't assume the elements take up any space.
+        try_decode_hash_set(decoder, decode_fn, 0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Encoding, T> TryDecode<E> for HashSet<T>
+    where T: TryDecode<E> + Eq + Hash,
+{
+    const MIN_ENCODED_SIZE: usize = 1;
+
     fn try_decode(decoder: &mut Decoder<E>) -> DecodeResult<Self> {
-        let length = E::try_decode_size(decoder)?;
-        let buffer = decoder.read_bytes_exact(length as usize)?.to_vec();                                 // TODO can be bad
+        try_decode_hash_set(decoder, T::try_decode, T::MIN_ENCODED_SIZE)
+    }
+}
+
+#[cfg(feature = "std")]
+fn try_decode_hash_set<E: Encoding, T: Eq + Hash>(
+    decoder: &mut Decoder<E>,
+    decode_fn: DecodeFn<T, E>,
+    min_element_size: usize,
+) -> DecodeResult<HashSet<T>> {
+    decoder.nested(|decoder| {
+        let length = decoder.try_decode_collection_length(min_element_size)?;
+        decoder.increase_heap_allocation_total_for::<T>(length)?;
+        let mut hash_set = HashSet::with_capacity(length);
+
+        for i in 0..length {
+            let offset = decoder.position();
+            let element = decode_fn(decoder).at_index(i)?;
+            if !hash_set.insert(element) && decoder.is_strict() {
+                let kind = DecodeErrorKind::InvalidData { desc: "sets cannot contain duplicate elements" };
+                return Err(DecodeError::new(kind, offset)).at_index(i);
+            }
+        }
+        Ok(hash_set)
+    })
+}
+
+// =============================================================================
+// Borrowed type implementations
+// =============================================================================
+
+impl<'a, E: Encoding> TryDecodeBorrowed<'a, E> for &'a [u8] {
+    /// Decodes a sequence of bytes by returning a slice of the decoder's input, without copying it.
+    fn try_decode_borrowed(decoder: &mut Decoder<'a, E>) -> DecodeResult<Self> {
+        let length = decoder.try_decode_collection_length(1)?;
+        decoder.read_bytes_exact(length)
+    }
+}
+
+impl<'a, E: Encoding> TryDecodeBorrowed<'a, E> for &'a str {
+    /// Decodes a string by validating that it's UTF-8, then returning it as a slice of the decoder's input, without
+    /// copying it.
+    fn try_decode_borrowed(decoder: &mut Decoder<'a, E>) -> DecodeResult<Self> {
+        let length = decoder.try_decode_collection_length(1)?;
+        let offset = decoder.position();
+        let bytes = decoder.read_bytes_exact(length)?;
 
-        String::from_utf8(buffer).map_err(|_| DecodeError::InvalidData {
-            desc: "encountered invalid utf-8 while decoding string",
+        core::str::from_utf8(bytes).map_err(|_| {
+            let kind = DecodeErrorKind::InvalidData { desc: "encountered invalid utf-8 while decoding string" };
+            DecodeError::new(kind, offset)
         })
     }
 }
+
+#[cfg(feature = "alloc")]
+impl<'a, E: Encoding> TryDecodeBorrowed<'a, E> for Cow<'a, [u8]> {
+    /// Delegates to the implementation for `&[u8]`, always returning [`Cow::Borrowed`].
+    #[inline(always)]
+    fn try_decode_borrowed(decoder: &mut Decoder<'a, E>) -> DecodeResult<Self> {
+        <&[u8]>::try_decode_borrowed(decoder).map(Cow::Borrowed)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, E: Encoding> TryDecodeBorrowed<'a, E> for Cow<'a, str> {
+    /// Delegates to the implementation for `&str`, always returning [`Cow::Borrowed`].
+    #[inline(always)]
+    fn try_decode_borrowed(decoder: &mut Decoder<'a, E>) -> DecodeResult<Self> {
+        <&str>::try_decode_borrowed(decoder).map(Cow::Borrowed)
+    }
+}
+
+// =============================================================================
+// Pointer type implementations
+// =============================================================================
+
+#[cfg(feature = "alloc")]
+impl<E: Encoding, T> TryDecode<E> for Box<T>
+    where T: TryDecode<E>,
+{
+    const MIN_ENCODED_SIZE: usize = T::MIN_ENCODED_SIZE;
+
+    fn try_decode(decoder: &mut Decoder<E>) -> DecodeResult<Self> {
+        decoder.nested(|decoder| {
+            decoder.increase_heap_allocation_total_for::<T>(1)?;
+            T::try_decode(decoder).map(Box::new)
+        })
+    }
+}
+
+// =============================================================================
+// Dictionary type implementations
+// =============================================================================
+
+#[cfg(feature = "alloc")]
+macro_rules! try_decode_dictionary_body {
+    ($encoding:ty, $decode_dictionary_fn:ident) => {
+        const MIN_ENCODED_SIZE: usize = 1;
+
+        fn try_decode(decoder: &mut Decoder<$encoding>) -> DecodeResult<Self> {
+            let decode_fn = |decoder: &mut Decoder<$encoding>| {
+                let key = K::try_decode(decoder)?;
+                let value = V::try_decode(decoder)?;
+                Ok((key, value))
+            };
+            let min_entry_size = K::MIN_ENCODED_SIZE.saturating_add(V::MIN_ENCODED_SIZE);
+            $decode_dictionary_fn(decoder, decode_fn, min_entry_size)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn try_decode_hash_map<E: Encoding, K: Eq + Hash, V>(
+    decoder: &mut Decoder<E>,
+    decode_fn: DecodeFn<(K, V), E>,
+    min_entry_size: usize,
+) -> DecodeResult<HashMap<K, V>> {
+    decoder.nested(|decoder| {
+        let length = decoder.try_decode_collection_length(min_entry_size)?;
+        decoder.increase_heap_allocation_total_for::<(K, V)>(length)?;
+        let mut hash_map = HashMap::with_capacity(length);
+
+        for i in 0..length {
+            let offset = decoder.position();
+            let (key, value) = decode_fn(decoder).at_index(i)?;
+            if hash_map.insert(key, value).is_some() && decoder.is_strict() {
+                return Err(duplicate_key_error(offset)).at_index(i);
+            }
+        }
+        Ok(hash_map)
+    })
+}
+
+#[cfg(feature = "std")]
+impl<E: Encoding, K, V> TryDecodeCollection<E, (K, V)> for HashMap<K, V>
+    where K: Eq + Hash,
+{
+    fn try_decode_with_fn(decoder: &mut Decoder<E>, decode_fn: DecodeFn<(K, V), E>) -> DecodeResult<Self> {
+        // We can't know how many bytes `decode_fn` reads, so we can't assume the entries take up any space.
+        try_decode_hash_map(decoder, decode_fn, 0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Encoding, K, V> TryDecode<E> for HashMap<K, V>
+where
+    K: TryDecode<E> + Eq + Hash,
+    V: TryDecode<E>,
+{
+    try_decode_dictionary_body!(E, try_decode_hash_map);
+}
+
+#[cfg(feature = "alloc")]
+pub(crate) fn try_decode_btree_map<E: Encoding, K: Ord, V>(
+    decoder: &mut Decoder<E>,
+    decode_fn: DecodeFn<(K, V), E>,
+    min_entry_size: usize,
+) -> DecodeResult<BTreeMap<K, V>> {
+    decoder.nested(|decoder| {
+        let length = decoder.try_decode_collection_length(min_entry_size)?;
+        // BTreeMaps don't pre-allocate, but each entry we insert is still stored on the heap.
+        decoder.increase_heap_allocation_total_for::<(K, V)>(length)?;
+        let mut btree_map = BTreeMap::new();
+
+        for i in 0..length {
+            let offset = decoder.position();
+            let (key, value) = decode_fn(decoder).at_index(i)?;
+            if btree_map.insert(key, value).is_some() && decoder.is_strict() {
+                return Err(duplicate_key_error(offset)).at_index(i);
+            }
+        }
+        Ok(btree_map)
+    })
+}
+
+/// Returns the error that strict decoders report when a dictionary contains the same key more than once.
+/// Non-strict decoders keep the last value for each key instead. See [`Decoder::with_strict_mode`].
+#[cfg(feature = "alloc")]
+fn duplicate_key_error(offset: usize) -> DecodeError {
+    let kind = DecodeErrorKind::InvalidData { desc: "dictionaries cannot contain duplicate keys" };
+    DecodeError::new(kind, offset)
+}
+
+#[cfg(feature = "alloc")]
+impl<E: Encoding, K, V> TryDecodeCollection<E, (K, V)> for BTreeMap<K, V>
+    where K: Ord,
+{
+    fn try_decode_with_fn(decoder: &mut Decoder<E>, decode_fn: DecodeFn<(K, V), E>) -> DecodeResult<Self> {
+        // We can't know how many bytes `decode_fn` reads, so we can't assume the entries take up any space.
+        try_decode_btree_map(decoder, decode_fn, 0)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<E: Encoding, K, V> TryDecode<E> for BTreeMap<K, V>
+where
+    K: TryDecode<E> + Ord,
+    V: TryDecode<E>,
+{
+    try_decode_dictionary_body!(E, try_decode_btree_map);
+}
+
+#[cfg(all(test, feature = "slice2", feature = "alloc"))]
+mod tests {
+    use crate::decoder::{Decoder, DecodeErrorKind, PathSegment};
+    use crate::encoder::Encoder;
+    use crate::slice2::Slice2;
+    use crate::Encoding;
+    use crate::try_decode::{TryDecode, TryDecodeCollection};
+    use alloc::collections::BTreeMap;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    // A varuint62 size of `2^40`, encoded on 8 bytes.
+    const HUGE_LENGTH: &[u8] = &[0b0000_0011, 0, 0, 0, 0, 0b0000_0100, 0, 0];
+
+    #[test]
+    fn huge_length_prefixes_are_rejected_before_allocating() {
+        let mut decoder: Decoder<Slice2> = Decoder::new(HUGE_LENGTH);
+        let error = decoder.try_decode::<Vec<i64>>().unwrap_err();
+        assert!(matches!(error.kind(), DecodeErrorKind::InvalidData { .. }));
+        assert_eq!(error.offset(), 0);
+
+        let mut decoder: Decoder<Slice2> = Decoder::new(HUGE_LENGTH);
+        let error = decoder.try_decode::<String>().unwrap_err();
+        assert!(matches!(error.kind(), DecodeErrorKind::InvalidData { .. }));
+        assert_eq!(error.offset(), 0);
+    }
+
+    #[test]
+    fn huge_length_prefixes_hit_the_allocation_limit_instead_of_allocating() {
+        // Custom decode functions don't declare a minimum size, so only the allocation limit can catch these.
+        let mut decoder: Decoder<Slice2> = Decoder::new(HUGE_LENGTH);
+        let error = Vec::<i64>::try_decode_with_fn(&mut decoder, i64::try_decode).unwrap_err();
+        assert!(matches!(error.kind(), DecodeErrorKind::HeapAllocationLimitReached { requested, .. } if *requested == 8 << 40));
+    }
+
+    #[test]
+    fn well_formed_payloads_are_within_the_default_allocation_limit() {
+        use crate::decoder::{DecodeResult, DEFAULT_MAX_DEPTH};
+        use alloc::vec;
+
+        /// A tree of sequences, which can be nested arbitrarily deeply.
+        #[derive(Debug)]
+        struct Tree(Vec<Tree>);
+
+        impl Tree {
+            fn depth(&self) -> usize {
+                1 + self.0.iter().map(Tree::depth).max().unwrap_or(0)
+            }
+        }
+
+        impl TryDecode<Slice2> for Tree {
+            const MIN_ENCODED_SIZE: usize = 1;
+
+            fn try_decode(decoder: &mut Decoder<Slice2>) -> DecodeResult<Self> {
+                Vec::try_decode(decoder).map(Tree)
+            }
+        }
+
+        // Sequences nested as deeply as decoders allow, where each one holds a single sequence, except the innermost.
+        let mut buffer = vec![4; DEFAULT_MAX_DEPTH - 1];
+        buffer.push(0);
+        let mut decoder: Decoder<Slice2> = Decoder::new(&buffer);
+        assert_eq!(decoder.try_decode::<Tree>().unwrap().depth(), DEFAULT_MAX_DEPTH);
+
+        // A sequence of 64 empty dictionaries, each of which is much larger in memory than its 1 byte encoding.
+        let mut buffer = vec![1, 1];
+        buffer.extend([0; 64]);
+        let mut decoder: Decoder<Slice2> = Decoder::new(&buffer);
+        let dictionaries = decoder.try_decode::<Vec<BTreeMap<String, Vec<Tree>>>>().unwrap();
+        assert_eq!(dictionaries.len(), 64);
+    }
+
+    #[test]
+    fn strict_decoders_reject_duplicate_dictionary_keys() {
+        // A dictionary with 2 entries, which both have a key of `1`.
+        let buffer: &[u8] = &[8, 1, 5, 1, 6];
+
+        let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+        let dictionary = decoder.try_decode::<BTreeMap<u8, u8>>().unwrap();
+        assert_eq!(dictionary.get(&1), Some(&6));
+
+        let mut decoder: Decoder<Slice2> = Decoder::new(buffer).with_strict_mode();
+        let error = decoder.try_decode::<BTreeMap<u8, u8>>().unwrap_err();
+        assert!(matches!(error.kind(), DecodeErrorKind::InvalidData { .. }));
+        assert_eq!(error.offset(), 3);
+        assert!(error.path().eq(&[PathSegment::Index(1)]));
+    }
+
+    #[test]
+    fn numeric_sequences_round_trip_through_the_bulk_path() {
+        let values: Vec<f64> = (0..100).map(|i| i as f64 * -1.5).collect();
+        let mut encoder: Encoder<Slice2> = Encoder::new();
+        encoder.try_encode(&values).unwrap();
+        let buffer = encoder.into_bytes();
+
+        // The bulk path must produce exactly what encoding each element individually would.
+        let mut expected: Encoder<Slice2> = Encoder::new();
+        Slice2::try_encode_size(values.len(), &mut expected).unwrap();
+        for value in &values {
+            expected.try_encode(*value).unwrap();
+        }
+        assert_eq!(buffer, expected.into_bytes());
+
+        let mut decoder: Decoder<Slice2> = Decoder::new(&buffer);
+        assert_eq!(decoder.try_decode::<Vec<f64>>().unwrap(), values);
+        assert_eq!(decoder.remaining(), 0);
+
+        // Truncating the last element must cause an error, instead of silently dropping it.
+        let mut decoder: Decoder<Slice2> = Decoder::new(&buffer[..buffer.len() - 1]);
+        assert!(decoder.try_decode::<Vec<f64>>().is_err());
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn bytes_are_only_shared_when_decoding_from_bytes() {
+        use bytes::Bytes;
+
+        // A sequence of 3 bytes: `[1, 2, 3]`.
+        let buffer = Bytes::from_static(&[12, 1, 2, 3]);
+
+        let mut decoder: Decoder<Slice2> = Decoder::from_bytes(&buffer);
+        let shared = decoder.try_decode::<Bytes>().unwrap();
+        assert_eq!(shared.as_ptr(), buffer[1..].as_ptr());
+        assert_eq!(decoder.remaining_heap_allocation_size(), 32);
+
+        // Decoders over plain slices have no `Bytes` to share, so they copy the bytes, which counts as an allocation.
+        let mut decoder: Decoder<Slice2> = Decoder::new(&buffer);
+        let copied = decoder.try_decode::<Bytes>().unwrap();
+        assert_ne!(copied.as_ptr(), buffer[1..].as_ptr());
+        assert_eq!(decoder.remaining_heap_allocation_size(), 29);
+
+        assert_eq!(shared, copied);
+    }
+}
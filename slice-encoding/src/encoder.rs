@@ -1,6 +1,14 @@
 // Copyright (c) ZeroC, Inc.
 
 use crate::Encoding;
+use crate::io_types::{self, BufferedOutput};
+use crate::try_encode::TryEncode;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "bytes")]
+use bytes::BytesMut;
 
 /// TODO
 #[derive(Debug)]
@@ -8,25 +16,339 @@ pub struct Encoder<'a, E: Encoding> {
     /// Which version of the Slice encoding this encoder is using.
     encoding: E,
 
-    buffer_temp: core::marker::PhantomData<&'a ()>, // TODO
+    /// The output that this encoder writes its bytes into. It tracks how many bytes have been written so far.
+    output: BufferedOutput<'a>,
+
+    /// Whether this encoder sorts the elements of unordered collections. See [`Self::with_deterministic_mode`].
+    deterministic: bool,
+
+    /// The tag of the last tagged field that was encoded into the current struct, if any.
+    /// Tagged fields must be encoded in increasing order of their tags. See [`Self::start_tagged_field`].
+    previous_tag: Option<i32>,
 }
 
 impl<'a, E: Encoding> Encoder<'a, E> {
-    // TODO new function here?
+    /// Creates a new encoder that writes into an empty, growable buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let mut encoder: Encoder<Slice2> = Encoder::new();
+    /// assert!(encoder.as_bytes().is_empty());
+    ///
+    /// encoder.write_bytes(&[1, 2, 3]).unwrap();
+    /// assert_eq!(encoder.as_bytes(), &[1, 2, 3]);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates a new encoder that writes into a growable buffer which has space pre-allocated for at least `capacity`
+    /// many bytes. This avoids re-allocating the buffer while encoding, as long as `capacity` is large enough.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let encoder: Encoder<Slice2> = Encoder::with_capacity(32);
+    ///
+    /// // Pre-allocating space doesn't write any bytes into the encoder.
+    /// assert!(encoder.as_bytes().is_empty());
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from(BufferedOutput::from(Vec::with_capacity(capacity)))
+    }
+
+    /// Creates a new encoder that writes into the provided buffer, starting at the beginning of the buffer.
+    ///
+    /// The encoder never writes past the end of `buffer`. If a value doesn't fit in the remaining space, encoding it
+    /// returns [`InsufficientSpace`](EncodeError::InsufficientSpace) instead. This doesn't require heap allocation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::{Encoder, EncodeError};
+    /// # use slice_encoding::slice2::Slice2;
+    /// let mut buffer = [0; 6];
+    /// let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+    ///
+    /// assert_eq!(encoder.try_encode(9_i32), Ok(()));
+    /// assert_eq!(encoder.position(), 4);
+    ///
+    /// // There are only 2 bytes left, so a 4 byte value doesn't fit, and nothing is written.
+    /// assert_eq!(encoder.try_encode(3_i32), Err(EncodeError::InsufficientSpace { requested: 4, remaining: 2 }));
+    /// assert_eq!(encoder.position(), 4);
+    ///
+    /// // But smaller values can still be written.
+    /// assert_eq!(encoder.try_encode(3_i16), Ok(()));
+    /// assert_eq!(encoder.as_bytes(), &[9, 0, 0, 0, 3, 0]);
+    /// ```
+    pub fn from_slice(buffer: &'a mut [u8]) -> Self {
+        Self::from(BufferedOutput::from(buffer))
+    }
+
+    /// Creates a new encoder that writes into the provided [`BytesMut`], after any bytes it already holds.
+    ///
+    /// Once encoding is finished, the buffer can be retrieved with [`into_bytes_mut`](Self::into_bytes_mut), and then
+    /// frozen into a [`Bytes`](bytes::Bytes) to share it without copying.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use bytes::BytesMut;
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let mut encoder: Encoder<Slice2> = Encoder::from_bytes_mut(BytesMut::with_capacity(16));
+    /// encoder.try_encode(true).unwrap();
+    /// encoder.try_encode(7_i32).unwrap();
+    ///
+    /// let bytes = encoder.into_bytes_mut().freeze();
+    /// assert_eq!(bytes, &[1, 7, 0, 0, 0][..]);
+    /// ```
+    #[cfg(feature = "bytes")]
+    pub fn from_bytes_mut(buffer: BytesMut) -> Self {
+        Self::from(BufferedOutput::from(buffer))
+    }
+
+    /// Enables deterministic mode on this encoder, then returns it by value.
+    ///
+    /// By default, unordered collections (like [`HashMap`] and [`HashSet`]) are encoded in whatever order they're
+    /// iterated in, which can differ between two collections holding the same elements. In deterministic mode, their
+    /// elements are instead encoded in order of their encoded bytes, so that equal collections always produce identical
+    /// bytes. This requires encoding each element into a temporary buffer first, so it's slower.
+    ///
+    /// Since Slice encodings are self-delimiting, sorting dictionary entries by their encoded bytes is equivalent to
+    /// sorting them by their encoded keys.
+    ///
+    /// [`HashMap`]: std::collections::HashMap
+    /// [`HashSet`]: std::collections::HashSet
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(feature = "std"), doc = "```ignore")]
+    #[cfg_attr(feature = "std", doc = "```")]
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// # use std::collections::HashMap;
+    /// // Every `HashMap` is seeded differently, so these iterate over their entries in different orders.
+    /// let map: HashMap<i32, bool> = (0..100).map(|i| (i, i % 2 == 0)).collect();
+    /// let other: HashMap<i32, bool> = (0..100).rev().map(|i| (i, i % 2 == 0)).collect();
+    ///
+    /// let mut encoder: Encoder<Slice2> = Encoder::new().with_deterministic_mode();
+    /// encoder.try_encode(&map).unwrap();
+    ///
+    /// let mut other_encoder: Encoder<Slice2> = Encoder::new().with_deterministic_mode();
+    /// other_encoder.try_encode(&other).unwrap();
+    ///
+    /// assert_eq!(encoder.as_bytes(), other_encoder.as_bytes());
+    /// ```
+    pub fn with_deterministic_mode(mut self) -> Self {
+        self.deterministic = true;
+        self
+    }
+
+    /// Returns `true` if this encoder is in deterministic mode. See [`Self::with_deterministic_mode`].
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Returns the version of the Slice encoding this encoder is using.
+    pub fn encoding(&self) -> E {
+        self.encoding
+    }
+
+    /// Returns the number of bytes that have been written into this encoder so far.
+    pub fn position(&self) -> usize {
+        self.output.position()
+    }
+
+    /// Returns the number of bytes that can still be written into this encoder, or `None` if its buffer can grow.
+    ///
+    /// # Examples
+    ///
+    #[cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+    #[cfg_attr(feature = "alloc", doc = "```")]
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let mut buffer = [0; 8];
+    /// let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+    ///
+    /// encoder.write_bytes(&[1, 2, 3]).unwrap();
+    /// assert_eq!(encoder.remaining_capacity(), Some(5));
+    ///
+    /// // Encoders that write into a growable buffer have no fixed capacity.
+    /// let encoder: Encoder<Slice2> = Encoder::new();
+    /// assert_eq!(encoder.remaining_capacity(), None);
+    /// ```
+    pub fn remaining_capacity(&self) -> Option<usize> {
+        self.output.remaining_capacity()
+    }
+
+    /// Returns a slice containing all the bytes that have been written into this encoder so far.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.output.as_bytes()
+    }
+
+    /// Consumes this encoder, returning a vector containing all the bytes that were written into it.
+    ///
+    /// If this encoder was writing into a caller-provided buffer (see [`from_slice`](Self::from_slice)), the written
+    /// bytes are copied into a new vector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let mut encoder: Encoder<Slice2> = Encoder::new();
+    /// encoder.try_encode(true).unwrap();
+    /// encoder.try_encode(7_i32).unwrap();
+    ///
+    /// assert_eq!(encoder.into_bytes(), vec![1, 7, 0, 0, 0]);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.output.into_vec()
+    }
+
+    /// Consumes this encoder, returning a [`BytesMut`] containing all the bytes that were written into it.
+    ///
+    /// If this encoder wasn't writing into a `BytesMut` (see [`from_bytes_mut`](Self::from_bytes_mut)), the written
+    /// bytes are copied into a new one.
+    #[cfg(feature = "bytes")]
+    pub fn into_bytes_mut(self) -> BytesMut {
+        self.output.into_bytes_mut()
+    }
 
     /// TODO
+    pub fn try_encode<T: TryEncode<E>>(&mut self, value: T) -> EncodeResult<()> {
+        value.try_encode(self)
+    }
+
+    /// Writes a single byte into this encoder's buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let mut buffer = [0; 16];
+    /// let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+    ///
+    /// assert_eq!(encoder.write_byte(4), Ok(()));
+    /// assert_eq!(encoder.write_byte(2), Ok(()));
+    /// assert_eq!(encoder.as_bytes(), &[4, 2]);
+    /// ```
     pub fn write_byte(&mut self, data: u8) -> EncodeResult<()> {
-        todo!() // TODO
+        self.write_bytes(&[data])
     }
 
-    /// TODO
+    /// Writes all the provided bytes into this encoder's buffer, in order.
+    ///
+    /// If there isn't enough space left in the buffer for all of them, this returns `Err` instead, and nothing is
+    /// written. This can only happen for encoders that write into a fixed-size buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let mut buffer = [0; 16];
+    /// let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+    ///
+    /// assert_eq!(encoder.write_bytes(&[1, 2, 3]), Ok(()));
+    /// assert_eq!(encoder.write_bytes(&[]), Ok(()));
+    /// assert_eq!(encoder.write_bytes(&[4]), Ok(()));
+    /// assert_eq!(encoder.as_bytes(), &[1, 2, 3, 4]);
+    /// ```
     pub fn write_bytes(&mut self, data: &[u8]) -> EncodeResult<()> {
-        todo!() // TODO
+        Ok(self.output.write_bytes(data)?)
     }
 
-    /// TODO
-    pub fn reserve(&mut self, count: usize) -> EncodeResult<&'a mut [u8]> {
-        todo!() // TODO
+    /// Writes `count`-many zeroed bytes into this encoder's buffer, then returns a mutable slice over them.
+    ///
+    /// This is useful when the value of some bytes isn't known until after they've been written, like the bits of a
+    /// bit sequence. The caller can fill in the reserved bytes later on.
+    ///
+    /// If there isn't enough space left in the buffer for `count`-many bytes, this returns `Err` instead, and nothing
+    /// is written. This can only happen for encoders that write into a fixed-size buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let mut buffer = [0; 16];
+    /// let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+    /// encoder.write_byte(9).unwrap();
+    ///
+    /// let reserved = encoder.reserve(2).unwrap();
+    /// assert_eq!(reserved, &[0, 0]);
+    /// reserved[1] = 5;
+    ///
+    /// assert_eq!(encoder.as_bytes(), &[9, 0, 5]);
+    /// ```
+    pub fn reserve(&mut self, count: usize) -> EncodeResult<&mut [u8]> {
+        Ok(self.output.reserve(count)?)
+    }
+
+    /// Returns a mutable slice containing all the bytes that have been written into this encoder so far.
+    ///
+    /// This is used to fill in placeholders whose value isn't known until after more bytes have been written, like the
+    /// size of a tagged field.
+    pub(crate) fn as_bytes_mut(&mut self) -> &mut [u8] {
+        self.output.as_bytes_mut()
+    }
+
+    /// Checks that a tagged field with the provided tag can be encoded after the tagged fields that have already been
+    /// encoded into the current struct. Any encoding logic for tagged fields must call this function before encoding
+    /// one, and then call [`end_tagged_field`](Self::end_tagged_field) once it's finished.
+    ///
+    /// Tagged fields must be encoded in strictly increasing order of their tags, so if `tag` isn't greater than the
+    /// previous tag, this returns [`TagOrderViolation`](EncodeError::TagOrderViolation) instead.
+    pub(crate) fn start_tagged_field(&mut self, tag: i32) -> EncodeResult<()> {
+        match self.previous_tag {
+            Some(previous) if tag <= previous => Err(EncodeError::TagOrderViolation { previous, current: tag }),
+            // The field's value might be a struct with tagged fields of its own, which are ordered separately.
+            _ => {
+                self.previous_tag = None;
+                Ok(())
+            }
+        }
+    }
+
+    /// Marks the end of a tagged field, which was started by calling [`start_tagged_field`](Self::start_tagged_field).
+    pub(crate) fn end_tagged_field(&mut self, tag: i32) {
+        self.previous_tag = Some(tag);
+    }
+
+    /// Marks the end of the current struct's tagged fields, so the next tagged field can have any tag.
+    pub(crate) fn end_tagged_fields(&mut self) {
+        self.previous_tag = None;
+    }
+}
+
+impl<'a, E: Encoding> From<BufferedOutput<'a>> for Encoder<'a, E> {
+    /// Creates a new encoder that writes bytes into the provided output.
+    fn from(output: BufferedOutput<'a>) -> Self {
+        Encoder {
+            encoding: E::default(),
+            output,
+            deterministic: false,
+            previous_tag: None,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<E: Encoding> Default for Encoder<'_, E> {
+    /// Creates a new encoder that writes into an empty, growable buffer. This is equivalent to [`Encoder::new`].
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -35,8 +357,113 @@ impl<'a, E: Encoding> Encoder<'a, E> {
 /// It is a direct mapping to [`std::result::Result`] with an `Err` type of [`EncodeError`].
 pub type EncodeResult<T> = Result<T, EncodeError>;
 
-/// TODO
-#[derive(Debug)]
+/// The error type for encoding operations.
+///
+/// It is shared by all encoding paths: writing into fixed-size or growable buffers, and both versions of the Slice
+/// encoding. Each variant describes a different reason why a value couldn't be encoded.
+#[derive(Debug, PartialEq, Eq)]
 pub enum EncodeError {
+    /// The encoder's buffer doesn't have enough space left to hold the bytes being written into it.
+    ///
+    /// This can only occur for encoders that write into a fixed-size buffer.
+    InsufficientSpace {
+        /// The number of bytes that were attempted to be written.
+        requested: usize,
+        /// The number of bytes that were left in the encoder's buffer.
+        remaining: usize,
+    },
+
+    /// A value was outside the range of values that can be encoded by the type it was being encoded as.
+    /// For example, attempting to encode `u64::MAX` as a `varuint62`.
+    OutOfRange {
+        /// The value that was being encoded.
+        value: i128,
+        /// The minimum value that can be encoded by the type.
+        min: i128,
+        /// The maximum value that can be encoded by the type.
+        max: i128,
+        /// The name of the Slice type the value was being encoded as.
+        typename: &'static str,
+    },
+
+    /// A collection had more elements than can be represented by the size prefix that precedes it.
+    CollectionTooLarge {
+        /// The number of elements in the collection.
+        length: usize,
+        /// The maximum number of elements that the size prefix can represent.
+        max: u64,
+    },
+
+    /// A type was encoded with a version of the Slice encoding that cannot represent it.
+    UnsupportedType {
+        /// The name of the type that was being encoded.
+        typename: &'static str,
+        /// A description of why the type cannot be encoded.
+        desc: &'static str,
+    },
+
+    /// Tagged fields were encoded out of order. Tagged fields must be encoded in strictly increasing order of their
+    /// tags, and each tag can only be encoded once.
+    TagOrderViolation {
+        /// The tag of the previously encoded tagged field.
+        previous: i32,
+        /// The tag of the tagged field that was being encoded.
+        current: i32,
+    },
 
+    /// Writing to the encoder's output failed.
+    #[cfg(feature = "std")]
+    Io {
+        /// The kind of I/O error that occurred.
+        kind: std::io::ErrorKind,
+    },
+}
+
+impl core::fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            EncodeError::InsufficientSpace { requested, remaining } => write!(
+                f,
+                "insufficient space in buffer: attempted to write {requested} bytes, but only {remaining} remain",
+            ),
+            EncodeError::OutOfRange { value, min, max, typename } => write!(
+                f,
+                "value '{value}' is out of range for '{typename}': must be between '{min}' and '{max}' (inclusive)",
+            ),
+            EncodeError::CollectionTooLarge { length, max } => write!(
+                f,
+                "collection of {length} elements is too large: its size prefix can hold at most {max} elements",
+            ),
+            EncodeError::UnsupportedType { typename, desc } => write!(
+                f,
+                "cannot encode '{typename}': {desc}",
+            ),
+            EncodeError::TagOrderViolation { previous, current } => write!(
+                f,
+                "tagged field with tag '{current}' was encoded after a tagged field with tag '{previous}': \
+                tagged fields must be encoded in increasing order of their tags",
+            ),
+            #[cfg(feature = "std")]
+            EncodeError::Io { kind } => write!(f, "failed to write output: {kind}"),
+        }
+    }
+}
+
+impl core::error::Error for EncodeError {}
+
+impl From<io_types::Error> for EncodeError {
+    fn from(error: io_types::Error) -> Self {
+        match error {
+            io_types::Error::InsufficientSpace { requested, remaining } => {
+                EncodeError::InsufficientSpace { requested, remaining }
+            }
+            #[cfg(feature = "std")]
+            io_types::Error::Io(kind) => EncodeError::Io { kind },
+            // Only inputs can reach end-of-file. Outputs report running out of space with `InsufficientSpace` instead.
+            io_types::Error::UnexpectedEof => unreachable!("outputs never report end-of-file"),
+            // Only inputs store the bytes they read, so only they have a storage limit.
+            #[cfg(feature = "std")]
+            io_types::Error::StorageLimitReached { .. } => unreachable!("outputs never store the bytes they read"),
+        }
+    }
 }
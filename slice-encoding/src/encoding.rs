@@ -1 +1,360 @@
 // Copyright (c) ZeroC, Inc.
+
+use crate::encoder::{EncodeResult, Encoder};
+use crate::Encoding;
+use crate::try_encode::{EncodeFn, TryEncode, TryEncodeCollection};
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
+
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
+// =============================================================================
+// Fixed-length type implementations
+// =============================================================================
+
+// For primitive types, we implement `TryEncode` on the owned type, since it's conventional to pass these types
+// 'by value'. But we also want `encode` to be available when they're borrowed too.
+// This macro implements `TryEncode` on `&T` by delegating to the implementation for `T`.
+macro_rules! implement_slice_encodable_for_borrowed_value_type {
+    ($ty:ty, $encoding:ident$(: $($bounds:tt)+)?) => {
+        impl$(<$encoding: $($bounds)+>)? TryEncode<$encoding> for &$ty {
+            #[doc = concat!("Delegates to the implementation for `", stringify!($ty), "`.")]
+            #[inline(always)]
+            fn try_encode(self, encoder: &mut Encoder<$encoding>) -> EncodeResult<()> {
+                (*self).try_encode(encoder)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "slice2")]
+pub(crate) use implement_slice_encodable_for_borrowed_value_type;
+
+impl<E: Encoding> TryEncode<E> for bool {
+    /// Encodes a value of `0` for `false` and a value of `1` for true, on a single byte.
+    fn try_encode(self, encoder: &mut Encoder<E>) -> EncodeResult<()> {
+        // In memory, bools are guaranteed to be `0` for false and `1` for `true`.
+        encoder.write_byte(self as u8)
+    }
+}
+implement_slice_encodable_for_borrowed_value_type!(bool, E: Encoding);
+
+impl<E: Encoding> TryEncode<E> for u8 {
+    /// Writes this byte directly into the encoder, as-is.
+    fn try_encode(self, encoder: &mut Encoder<E>) -> EncodeResult<()> {
+        encoder.write_byte(self)
+    }
+}
+
+impl<E: Encoding> TryEncode<E> for &u8 {
+    /// Delegates to the implementation for `u8`.
+    #[inline(always)]
+    fn try_encode(self, encoder: &mut Encoder<E>) -> EncodeResult<()> {
+        (*self).try_encode(encoder)
+    }
+
+    /// Reserves space for all the bytes at once, then copies them in.
+    fn try_encode_elements<I>(elements: I, encoder: &mut Encoder<E>) -> EncodeResult<()>
+        where I: ExactSizeIterator<Item = Self>,
+    {
+        let buffer = encoder.reserve(elements.len())?;
+        for (dest, byte) in buffer.iter_mut().zip(elements) {
+            *dest = *byte;
+        }
+        Ok(())
+    }
+}
+
+/// This macro is for implementing `TryEncode` on a numeric primitive type (and borrows of it).
+/// Because all of these types have a `to_le_bytes` function that returns their representation in little endian.
+///
+/// Signed integers are always stored in two's compliment, and floating point numbers are always in IEEE 754 format.
+macro_rules! implement_slice_encodable_for_primitive_numeric_type {
+    ($ty:ty, $doc_text:literal, $encoding:ident$(: $($bounds:tt)+)?) => {
+        // Implement `TryEncode` for `ty`.
+        impl$(<$encoding: $($bounds)+>)? TryEncode<$encoding> for $ty {
+            #[doc = $doc_text]
+            fn try_encode(self, encoder: &mut Encoder<$encoding>) -> EncodeResult<()> {
+                encoder.write_bytes(&self.to_le_bytes())
+            }
+        }
+
+        // Implement `TryEncode` for `&ty`.
+        impl$(<$encoding: $($bounds)+>)? TryEncode<$encoding> for &$ty {
+            #[doc = concat!("Delegates to the implementation for `", stringify!($ty), "`.")]
+            #[inline(always)]
+            fn try_encode(self, encoder: &mut Encoder<$encoding>) -> EncodeResult<()> {
+                (*self).try_encode(encoder)
+            }
+
+            /// Reserves space for all the elements at once, then writes their bytes into it in fixed-size chunks.
+            fn try_encode_elements<I>(elements: I, encoder: &mut Encoder<$encoding>) -> EncodeResult<()>
+                where I: ExactSizeIterator<Item = Self>,
+            {
+                const SIZE: usize = core::mem::size_of::<$ty>();
+
+                let buffer = encoder.reserve(elements.len().saturating_mul(SIZE))?;
+                for (chunk, element) in buffer.chunks_exact_mut(SIZE).zip(elements) {
+                    chunk.copy_from_slice(&element.to_le_bytes());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "slice2")]
+pub(crate) use implement_slice_encodable_for_primitive_numeric_type;
+
+implement_slice_encodable_for_primitive_numeric_type!(
+    i16,
+    "Encodes this i16 on 2 bytes (little endian), in two's complement form.",
+    E: Encoding
+);
+implement_slice_encodable_for_primitive_numeric_type!(
+    i32,
+    "Encodes this i32 on 4 bytes (little endian), in two's complement form.",
+    E: Encoding
+);
+implement_slice_encodable_for_primitive_numeric_type!(
+    i64,
+    "Encodes this i64 on 8 bytes (little endian), in two's complement form.",
+    E: Encoding
+);
+implement_slice_encodable_for_primitive_numeric_type!(
+    f32,
+    "Encodes this f32 on 4 bytes (little endian), using the \"binary32\" representation defined in IEEE 754-2008.",
+    E: Encoding
+);
+implement_slice_encodable_for_primitive_numeric_type!(
+    f64,
+    "Encodes this f64 on 8 bytes (little endian), using the \"binary64\" representation defined in IEEE 754-2008.",
+    E: Encoding
+);
+
+// =============================================================================
+// Sequence type implementations
+// =============================================================================
+
+impl<E: Encoding> TryEncode<E> for &str {
+    /// Encodes this string by writing its length (encoded as a size), followed by its content (encoded in UTF-8 on
+    /// `length` many bytes).
+    fn try_encode(self, encoder: &mut Encoder<E>) -> EncodeResult<()> {
+        E::try_encode_size(self.len(), encoder)?;
+
+        // Strings are always stored as UTF-8 in memory.
+        encoder.write_bytes(self.as_bytes())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<E: Encoding> TryEncode<E> for &String {
+    /// Delegates to the implementation for `&str`.
+    #[inline(always)]
+    fn try_encode(self, encoder: &mut Encoder<E>) -> EncodeResult<()> {
+        self.as_str().try_encode(encoder)
+    }
+}
+
+impl<'a, E: Encoding, T> TryEncodeCollection<E, &'a T> for &'a [T] {
+    /// Encodes this slice as a Slice sequence by writing its length (encoded as a size), followed by its elements,
+    /// encoded in order using the provided `encode_fn` function.
+    fn try_encode_with_fn(self, encoder: &mut Encoder<E>, encode_fn: EncodeFn<&'a T, E>) -> EncodeResult<()> {
+        E::try_encode_size(self.len(), encoder)?;
+
+        for element in self {
+            encode_fn(element, encoder)?;
+        }
+        Ok(())
+    }
+}
+
+/// Automatically implement `TryEncode` for slices whose elements are also `TryEncode`.
+impl<'a, E: Encoding, T> TryEncode<E> for &'a [T]
+    where &'a T: TryEncode<E>,
+{
+    /// Encodes this slice as a Slice sequence by writing its length (encoded as a size), followed by its elements,
+    /// which are encoded using [`try_encode_elements`](TryEncode::try_encode_elements).
+    fn try_encode(self, encoder: &mut Encoder<E>) -> EncodeResult<()> {
+        E::try_encode_size(self.len(), encoder)?;
+        <&T>::try_encode_elements(self.iter(), encoder)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, E: Encoding, T> TryEncodeCollection<E, &'a T> for &'a Vec<T> {
+    /// Delegates to the implementation for `&[T]`.
+    #[inline(always)]
+    fn try_encode_with_fn(self, encoder: &mut Encoder<E>, encode_fn: EncodeFn<&'a T, E>) -> EncodeResult<()> {
+        self.as_slice().try_encode_with_fn(encoder, encode_fn)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, E: Encoding, T> TryEncode<E> for &'a Vec<T>
+    where &'a T: TryEncode<E>,
+{
+    /// Delegates to the implementation for `&[T]`.
+    #[inline(always)]
+    fn try_encode(self, encoder: &mut Encoder<E>) -> EncodeResult<()> {
+        self.as_slice().try_encode(encoder)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<E: Encoding> TryEncode<E> for &Bytes {
+    /// Delegates to the implementation for `&[u8]`.
+    #[inline(always)]
+    fn try_encode(self, encoder: &mut Encoder<E>) -> EncodeResult<()> {
+        self.as_ref().try_encode(encoder)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, E: Encoding, T> TryEncodeCollection<E, &'a T> for &'a HashSet<T> {
+    /// Encodes this HashSet as a Slice sequence by writing its length (encoded as a size), followed by its elements,
+    /// encoded using the provided `encode_fn` function, in an arbitrary order.
+    ///
+    /// If the encoder is in [deterministic mode](Encoder::with_deterministic_mode), the elements are instead encoded
+    /// in order of their encoded bytes.
+    fn try_encode_with_fn(self, encoder: &mut Encoder<E>, encode_fn: EncodeFn<&'a T, E>) -> EncodeResult<()> {
+        E::try_encode_size(self.len(), encoder)?;
+
+        if encoder.is_deterministic() {
+            return try_encode_sorted(encoder, self.iter(), encode_fn);
+        }
+        for element in self {
+            encode_fn(element, encoder)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, E: Encoding, T> TryEncode<E> for &'a HashSet<T>
+    where &'a T: TryEncode<E>,
+{
+    /// This delegates to [`try_encode_with_fn`](TryEncodeCollection::try_encode_with_fn), using the element type's
+    /// `try_encode` function.
+    fn try_encode(self, encoder: &mut Encoder<E>) -> EncodeResult<()> {
+        self.try_encode_with_fn(encoder, <&T>::try_encode)
+    }
+}
+
+/// Encodes each of the provided elements into its own temporary buffer, then writes these buffers into the encoder in
+/// sorted order. This is how unordered collections are encoded in [deterministic mode](Encoder::with_deterministic_mode).
+#[cfg(feature = "alloc")]
+fn try_encode_sorted<E: Encoding, T>(
+    encoder: &mut Encoder<E>,
+    elements: impl Iterator<Item = T>,
+    encode_fn: EncodeFn<T, E>,
+) -> EncodeResult<()> {
+    let mut encoded_elements = elements
+        .map(|element| {
+            // Elements can contain unordered collections of their own, so these encoders must be deterministic too.
+            let mut element_encoder = Encoder::<E>::new().with_deterministic_mode();
+            encode_fn(element, &mut element_encoder)?;
+            Ok(element_encoder.into_bytes())
+        })
+        .collect::<EncodeResult<Vec<_>>>()?;
+    encoded_elements.sort_unstable();
+
+    for encoded_element in encoded_elements {
+        encoder.write_bytes(&encoded_element)?;
+    }
+    Ok(())
+}
+
+// =============================================================================
+// Pointer type implementations
+// =============================================================================
+
+#[cfg(feature = "alloc")]
+impl<'a, E: Encoding, T> TryEncode<E> for &'a Box<T>
+    where &'a T: TryEncode<E>,
+{
+    /// Delegates to the implementation for `&T`, since boxing a value doesn't affect its encoding.
+    #[inline(always)]
+    fn try_encode(self, encoder: &mut Encoder<E>) -> EncodeResult<()> {
+        self.as_ref().try_encode(encoder)
+    }
+}
+
+// =============================================================================
+// Dictionary type implementations
+// =============================================================================
+
+/// This macro is for implementing `TryEncodeCollection` on dictionary types, since their implementations are
+/// identical. Unfortunately, Rust doesn't have a `Dictionary` trait that could be used for a blanket impl instead.
+///
+/// This macro also implements `TryEncode` for dictionaries of other `TryEncode` types.
+///
+/// Dictionaries which are `$unordered` have their entries sorted when the encoder is in deterministic mode.
+macro_rules! implement_slice_encodable_for_dictionary_type {
+    ($ty:ident, $feature:literal, $order_doc:literal, $unordered:literal) => {
+
+#[cfg(feature = $feature)]
+impl<'a, E: Encoding, K, V> TryEncodeCollection<E, (&'a K, &'a V)> for &'a $ty<K, V> {
+    #[doc = concat!(
+"Encodes this ", stringify!($ty), " as a Slice dictionary by writing its length (encoded as a size), followed
+by its entries, encoded in ", $order_doc, ".
+An entry is encoded by first encoding its key, followed by its value. This is equivalent to the following slice:
+```slice
+compact struct Entry
+{
+    key: K
+    value: V
+}
+```"
+    )]
+    fn try_encode_with_fn(self, encoder: &mut Encoder<E>, encode_fn: EncodeFn<(&'a K, &'a V), E>) -> EncodeResult<()> {
+        E::try_encode_size(self.len(), encoder)?;
+
+        if $unordered && encoder.is_deterministic() {
+            return try_encode_sorted(encoder, self.iter(), encode_fn);
+        }
+        for entry in self {
+            encode_fn(entry, encoder)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = $feature)]
+#[doc = concat!("Automatically implement `TryEncode` for ", stringify!($ty), "s whose keys and values are both `TryEncode`.")]
+impl<'a, E: Encoding, K, V> TryEncode<E> for &'a $ty<K, V>
+where
+    &'a K: TryEncode<E>,
+    &'a V: TryEncode<E>,
+{
+    /// This delegates to [`try_encode_with_fn`](TryEncodeCollection::try_encode_with_fn), using the `try_encode`
+    /// functions of the key and value types.
+    fn try_encode(self, encoder: &mut Encoder<E>) -> EncodeResult<()> {
+        self.try_encode_with_fn(encoder, |(key, value), encoder| {
+            key.try_encode(encoder)?;
+            value.try_encode(encoder)
+        })
+    }
+}
+
+    }
+}
+
+implement_slice_encodable_for_dictionary_type!(
+    HashMap,
+    "std",
+    "an arbitrary order (or in order of their encoded bytes, if the encoder is in deterministic mode)",
+    true
+);
+implement_slice_encodable_for_dictionary_type!(BTreeMap, "alloc", "order", false);
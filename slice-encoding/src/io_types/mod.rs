@@ -2,12 +2,31 @@
 
 pub mod bit_sequence;
 
+// Reading from `std::io::Read` implementations requires the standard library.
+#[cfg(feature = "std")]
+pub mod reader;
+
+#[derive(Debug)]
 pub enum Error {
     UnexpectedEof,
     InsufficientSpace {
         requested: usize,
         remaining: usize,
-    }
+    },
+    /// Reading from the underlying input source failed with the provided error.
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+    /// Storing the bytes read from the underlying input source would exceed its storage limit.
+    /// See [`BufferedInput::set_storage_limit`].
+    #[cfg(feature = "std")]
+    StorageLimitReached {
+        /// The maximum number of bytes the input can store.
+        limit: usize,
+        /// The number of bytes the input has stored so far.
+        stored: usize,
+        /// The number of additional bytes that storing the requested bytes would have needed.
+        requested: usize,
+    },
 }
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -16,96 +35,202 @@ pub type Result<T> = core::result::Result<T, Error>;
 
 
 #[cfg(feature = "bytes")]
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 
+#[cfg(feature = "std")]
+use reader::{ReadBuffer, ReaderSource};
 
+#[derive(Debug)]
 pub struct BufferedInput<'a> {
     source: InputSource<'a>,
     buffer: &'a [u8],
+    /// The position of the next unread byte in `buffer`. This is always less than or equal to `buffer.len()`.
     pos: usize,
+    /// The number of bytes that were consumed before the start of `buffer`. This is always `0` for slices, but input
+    /// sources that refill over time move `buffer` forwards through their input as they go.
+    offset: usize,
 }
 
-impl BufferedInput<'_> {
-    pub fn peek_byte(&mut self) -> Result<&u8> {
-        if self.pos < self.buffer.len() {
-            Ok(&self.buffer[self.pos - 1])
-        } else {
-            Err(Error::UnexpectedEof)
+impl<'a> BufferedInput<'a> {
+    /// Creates a new input which reads bytes from `reader` as they're needed, and stores them in `storage`.
+    #[cfg(feature = "std")]
+    pub fn from_reader(reader: impl std::io::Read + 'a, storage: &'a ReadBuffer) -> Self {
+        BufferedInput {
+            source: InputSource::Reader(ReaderSource::new(reader, storage)),
+            buffer: &[],
+            pos: 0,
+            offset: 0,
         }
     }
 
-    pub fn read_byte(&mut self) -> Result<&u8> {
-        if self.pos < self.buffer.len() {
-            self.pos += 1;
-            Ok(&self.buffer[self.pos - 1])
-        } else {
-            Err(Error::UnexpectedEof)
+    /// Returns the number of bytes that have been consumed from this input so far.
+    pub fn position(&self) -> usize {
+        self.offset + self.pos
+    }
+
+    /// Returns the number of bytes remaining in this input, or [`usize::MAX`] if this input is filled over time, since
+    /// it can't know how many bytes are left.
+    pub fn remaining(&self) -> usize {
+        match self.is_streaming() {
+            true => usize::MAX,
+            false => self.unread().len(),
+        }
+    }
+
+    /// Returns the total number of bytes this input has allocated to hold the bytes it's read. This is always `0` for
+    /// inputs that aren't filled over time, since they don't allocate anything.
+    pub fn stored(&self) -> usize {
+        match &self.source {
+            #[cfg(feature = "std")]
+            InputSource::Reader(source) => source.stored(),
+            _ => 0,
+        }
+    }
+
+    /// Limits the total number of bytes this input can allocate to hold the bytes it reads, including what it's already
+    /// allocated. Reads which would need to allocate more than this fail with
+    /// [`StorageLimitReached`](Error::StorageLimitReached) instead. This is a no-op for inputs that aren't filled over
+    /// time, since they don't allocate anything.
+    #[cfg(feature = "std")]
+    pub fn set_storage_limit(&mut self, limit: usize) {
+        if let InputSource::Reader(source) = &mut self.source {
+            source.set_limit(limit);
+        }
+    }
+
+    /// Returns `true` if this input is filled over time (by reading from a stream), instead of being fixed up-front.
+    pub fn is_streaming(&self) -> bool {
+        match &self.source {
+            InputSource::Slice(_) => false,
+            #[cfg(feature = "bytes")]
+            InputSource::Bytes(_) => false,
+            #[cfg(feature = "std")]
+            InputSource::Reader(_) => true,
+        }
+    }
+
+    /// Returns the [`Bytes`] this input is reading from, if it was created from one.
+    ///
+    /// Slices of this input's buffer can be turned into reference-counted sub-slices of it with [`Bytes::slice_ref`],
+    /// instead of being copied.
+    #[cfg(feature = "bytes")]
+    pub fn source_bytes(&self) -> Option<&'a Bytes> {
+        match &self.source {
+            InputSource::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Tries to make at least `count`-many bytes available in `buffer` past the current position, by reading more bytes
+    /// from this input's source. This is a no-op for input sources that don't fill over time.
+    ///
+    /// Bytes are only guaranteed to be available if this returns `Ok`.
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    fn fill(&mut self, count: usize) -> Result<()> {
+        match &mut self.source {
+            InputSource::Slice(_) => Ok(()),
+            #[cfg(feature = "bytes")]
+            InputSource::Bytes(_) => Ok(()),
+            #[cfg(feature = "std")]
+            InputSource::Reader(source) => {
+                let unread = &self.buffer[self.pos..];
+                if unread.len() >= count {
+                    return Ok(());
+                }
+
+                let (buffer, result) = source.fill(unread, count);
+                self.offset += self.pos;
+                self.buffer = buffer;
+                self.pos = 0;
+                result
+            }
+        }
+    }
+
+    /// Moves this input back to the provided position, if it's still buffered, and returns whether it was.
+    pub fn rewind(&mut self, position: usize) -> bool {
+        match position.checked_sub(self.offset) {
+            Some(pos) if pos <= self.pos => {
+                self.pos = pos;
+                true
+            }
+            _ => false,
         }
     }
 
-    pub fn peek_bytes_exact<const N: usize>(&mut self) -> Result<&[u8; N]> {
+    pub fn peek_byte(&mut self) -> Result<&'a u8> {
+        self.fill(1)?;
+        self.buffer.get(self.pos).ok_or(Error::UnexpectedEof)
+    }
+
+    pub fn read_byte(&mut self) -> Result<&'a u8> {
+        self.fill(1)?;
+        let byte = self.buffer.get(self.pos).ok_or(Error::UnexpectedEof)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    pub fn peek_bytes_exact<const N: usize>(&mut self) -> Result<&'a [u8; N]> {
         let bytes = self.peek_byte_slice_exact(N)?;
         // SAFETY: unwrapping is safe because `peek_byte_slice_exact` guarantees `bytes` will have the correct length.
         Ok(bytes.try_into().unwrap())
     }
 
-    pub fn read_bytes_exact<const N: usize>(&mut self) -> Result<&[u8; N]> {
+    pub fn read_bytes_exact<const N: usize>(&mut self) -> Result<&'a [u8; N]> {
         let bytes = self.read_byte_slice_exact(N)?;
         // SAFETY: unwrapping is safe because `read_byte_slice_exact` guarantees `bytes` will have the correct length.
         Ok(bytes.try_into().unwrap())
     }
 
-    pub fn peek_byte_slice_exact(&mut self, count: usize) -> Result<&[u8]> {
-        let end = self.pos + count;
-        if end < self.buffer.len() {
-            // SAFETY: The necessary bound checks are performed by the above if statement.
-            unsafe {
-                Ok(&self.buffer.get_unchecked(self.pos..end))
-            }
-        } else {
-            Err(Error::InsufficientSpace {
-                requested: count,
-                remaining: self.buffer.len() - self.pos,
-            })
-        }
+    pub fn peek_byte_slice(&mut self, count: usize) -> &'a [u8] {
+        // Peeking is best-effort, so if filling fails, we just return as many bytes as are available.
+        let _ = self.fill(count);
+        let unread = self.unread();
+        &unread[..usize::min(count, unread.len())]
     }
 
-    pub fn read_byte_slice_exact(&mut self, count: usize) -> Result<&[u8]> {
-        let end = self.pos + count;
-        if end < self.buffer.len() {
-            // SAFETY: The necessary bound checks are performed by the above if statement.
-            unsafe {
-                let slice = &self.buffer.get_unchecked(self.pos..end);
-                self.pos = end;
-                Ok(slice)
-            }
+    pub fn peek_byte_slice_exact(&mut self, count: usize) -> Result<&'a [u8]> {
+        self.fill(count)?;
+        self.unread().get(..count).ok_or_else(|| self.insufficient_space_error(count))
+    }
+
+    pub fn read_byte_slice_exact(&mut self, count: usize) -> Result<&'a [u8]> {
+        let slice = self.peek_byte_slice_exact(count)?;
+        // `slice` is in bounds, so `self.pos + count` is at most `self.buffer.len()` and can't overflow.
+        self.pos += count;
+        Ok(slice)
+    }
+
+    /// Returns the next `count`-many bytes of this input, along with a new input over the bytes that come after them.
+    /// This input's position is left unchanged.
+    ///
+    /// If this input reads from a stream, the new input only holds the bytes that are already buffered, since only
+    /// one input can read from a stream at a time.
+    pub fn reserve(&mut self, count: usize) -> Result<(&'a [u8], Self)> {
+        self.fill(count)?;
+        let unread = self.unread();
+        if count <= unread.len() {
+            let (reserved, remaining) = unread.split_at(count);
+            Ok((reserved, BufferedInput::from(remaining)))
         } else {
-            Err(Error::InsufficientSpace {
-                requested: count,
-                remaining: self.buffer.len() - self.pos,
-            })
+            Err(self.insufficient_space_error(count))
         }
     }
 
-    pub fn reserve(&mut self, count: usize) -> Result<(&[u8], Self)> {
-        let split_pos = self.pos + count;
-
-        if split_pos < self.buffer.len() {
-            // SAFETY: The necessary bound checks are performed by the above if statement.
-            // TODO: simplify after 'https://github.com/rust-lang/rust/issues/76014' is stabilized.
-            let (reserved, remaining) = unsafe {
-                (self.buffer.get_unchecked(..split_pos), self.buffer.get_unchecked(split_pos..))
-            };
-            let child = match &self.source {
-                InputSource::Slice(_) => BufferedInput::from(remaining),
-            };
+    /// Returns the bytes in `buffer` which haven't been read yet.
+    fn unread(&self) -> &'a [u8] {
+        // `pos` is only ever advanced past bytes that are in `buffer`, so this can't panic. But we still index with a
+        // bounds check, so that a broken invariant causes a panic instead of undefined behavior.
+        &self.buffer[self.pos..]
+    }
 
-            Ok((reserved, child))
-        } else {
-            Err(Error::InsufficientSpace {
-                requested: count,
-                remaining: self.buffer.len() - self.pos,
-            })
+    fn insufficient_space_error(&self, requested: usize) -> Error {
+        Error::InsufficientSpace {
+            requested,
+            remaining: self.unread().len(),
         }
     }
 }
@@ -116,6 +241,7 @@ impl<'a> From<&'a [u8]> for BufferedInput<'a> {
             source: InputSource::Slice(slice),
             buffer: slice,
             pos : 0,
+            offset: 0,
         }
     }
 }
@@ -125,13 +251,309 @@ impl<'a> From<&'a [u8]> for BufferedInput<'a> {
 impl<'a> From<&'a Bytes> for BufferedInput<'a> {
     fn from(bytes: &'a Bytes) -> Self {
         BufferedInput {
-            source: InputSource::Slice(bytes),
+            source: InputSource::Bytes(bytes),
             buffer: bytes,
             pos : 0,
+            offset: 0,
         }
     }
 }
 
+#[derive(Debug)]
 pub enum InputSource<'a> {
     Slice(&'a [u8]),
+    /// A reference-counted buffer, which decoded byte sequences can share instead of copying.
+    #[cfg(feature = "bytes")]
+    Bytes(&'a Bytes),
+    /// Bytes which are read from a [`std::io::Read`] implementation as they're needed.
+    #[cfg(feature = "std")]
+    Reader(ReaderSource<'a>),
+}
+
+#[derive(Debug)]
+pub struct BufferedOutput<'a> {
+    target: OutputTarget<'a>,
+}
+
+impl BufferedOutput<'_> {
+    /// Returns the number of bytes that have been written into this output so far.
+    pub fn position(&self) -> usize {
+        match &self.target {
+            #[cfg(feature = "alloc")]
+            OutputTarget::Vec(vec) => vec.len(),
+            #[cfg(feature = "bytes")]
+            OutputTarget::BytesMut(bytes) => bytes.len(),
+            OutputTarget::Slice { pos, .. } => *pos,
+        }
+    }
+
+    /// Returns the number of additional bytes that can be written into this output, or `None` if it can grow.
+    pub fn remaining_capacity(&self) -> Option<usize> {
+        match &self.target {
+            #[cfg(feature = "alloc")]
+            OutputTarget::Vec(_) => None,
+            #[cfg(feature = "bytes")]
+            OutputTarget::BytesMut(_) => None,
+            OutputTarget::Slice { buffer, pos } => Some(buffer.len() - pos),
+        }
+    }
+
+    /// Returns a slice containing all the bytes that have been written into this output so far.
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.target {
+            #[cfg(feature = "alloc")]
+            OutputTarget::Vec(vec) => vec.as_slice(),
+            #[cfg(feature = "bytes")]
+            OutputTarget::BytesMut(bytes) => bytes,
+            OutputTarget::Slice { buffer, pos } => &buffer[..*pos],
+        }
+    }
+
+    /// Returns a mutable slice containing all the bytes that have been written into this output so far.
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        match &mut self.target {
+            #[cfg(feature = "alloc")]
+            OutputTarget::Vec(vec) => vec.as_mut_slice(),
+            #[cfg(feature = "bytes")]
+            OutputTarget::BytesMut(bytes) => bytes,
+            OutputTarget::Slice { buffer, pos } => &mut buffer[..*pos],
+        }
+    }
+
+    /// Writes all the provided bytes into this output. If there isn't enough space for all of them, nothing is written
+    /// and `Err` is returned instead.
+    pub fn write_bytes(&mut self, data: &[u8]) -> Result<()> {
+        self.reserve_uninitialized(data.len())?.copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Writes `count`-many zeroed bytes into this output, then returns a mutable slice over them.
+    /// If there isn't enough space for all of them, nothing is written and `Err` is returned instead.
+    pub fn reserve(&mut self, count: usize) -> Result<&mut [u8]> {
+        let reserved = self.reserve_uninitialized(count)?;
+        reserved.fill(0);
+        Ok(reserved)
+    }
+
+    /// Advances this output's position by `count` bytes and returns a mutable slice over them.
+    /// The contents of the returned slice are unspecified; callers are expected to overwrite all of it.
+    fn reserve_uninitialized(&mut self, count: usize) -> Result<&mut [u8]> {
+        match &mut self.target {
+            #[cfg(feature = "alloc")]
+            OutputTarget::Vec(vec) => {
+                let start = vec.len();
+                vec.resize(start + count, 0);
+                Ok(&mut vec[start..])
+            }
+            #[cfg(feature = "bytes")]
+            OutputTarget::BytesMut(bytes) => {
+                let start = bytes.len();
+                bytes.resize(start + count, 0);
+                Ok(&mut bytes[start..])
+            }
+            OutputTarget::Slice { buffer, pos } => {
+                let remaining = buffer.len() - *pos;
+                if count <= remaining {
+                    let start = *pos;
+                    *pos += count;
+                    Ok(&mut buffer[start..*pos])
+                } else {
+                    Err(Error::InsufficientSpace { requested: count, remaining })
+                }
+            }
+        }
+    }
+
+    /// Consumes this output, returning a vector containing all the bytes that were written into it.
+    /// If this output wasn't already backed by a vector, the bytes are copied into a new one.
+    #[cfg(feature = "alloc")]
+    pub fn into_vec(self) -> Vec<u8> {
+        match self.target {
+            OutputTarget::Vec(vec) => vec,
+            #[cfg(feature = "bytes")]
+            OutputTarget::BytesMut(bytes) => Vec::from(bytes),
+            OutputTarget::Slice { buffer, pos } => buffer[..pos].to_vec(),
+        }
+    }
+
+    /// Consumes this output, returning a [`BytesMut`] containing all the bytes that were written into it.
+    /// If this output wasn't already backed by a `BytesMut`, the bytes are copied into a new one.
+    #[cfg(feature = "bytes")]
+    pub fn into_bytes_mut(self) -> BytesMut {
+        match self.target {
+            OutputTarget::BytesMut(bytes) => bytes,
+            _ => BytesMut::from(self.as_bytes()),
+        }
+    }
+}
+
+impl<'a> From<&'a mut [u8]> for BufferedOutput<'a> {
+    fn from(buffer: &'a mut [u8]) -> Self {
+        BufferedOutput {
+            target: OutputTarget::Slice { buffer, pos: 0 },
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<Vec<u8>> for BufferedOutput<'_> {
+    fn from(vec: Vec<u8>) -> Self {
+        BufferedOutput {
+            target: OutputTarget::Vec(vec),
+        }
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl From<BytesMut> for BufferedOutput<'_> {
+    fn from(bytes: BytesMut) -> Self {
+        BufferedOutput {
+            target: OutputTarget::BytesMut(bytes),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum OutputTarget<'a> {
+    /// A growable buffer, which is owned by the output.
+    #[cfg(feature = "alloc")]
+    Vec(Vec<u8>),
+
+    /// A growable, reference-counted buffer, which is owned by the output.
+    #[cfg(feature = "bytes")]
+    BytesMut(BytesMut),
+
+    /// A fixed-size buffer, provided by the caller. `pos` is the number of bytes that have been written into it.
+    Slice {
+        buffer: &'a mut [u8],
+        pos: usize,
+    },
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Returns a buffer of random bytes, along with a position somewhere within it (including its end).
+    fn buffer_and_position() -> impl Strategy<Value = (Vec<u8>, usize)> {
+        proptest::collection::vec(any::<u8>(), 0..64).prop_flat_map(|buffer| {
+            let len = buffer.len();
+            (Just(buffer), 0..=len)
+        })
+    }
+
+    /// Returns a count which is usually close to the number of remaining bytes, but is occasionally huge, to check
+    /// that positions near `usize::MAX` can't overflow.
+    fn count_for(remaining: usize) -> impl Strategy<Value = usize> {
+        prop_oneof![
+            4 => 0..=remaining + 2,
+            1 => usize::MAX - 2..=usize::MAX,
+        ]
+    }
+
+    /// Returns an input over `buffer` which has already read `pos`-many bytes.
+    fn input_at(buffer: &[u8], pos: usize) -> BufferedInput<'_> {
+        let mut input = BufferedInput::from(buffer);
+        input.read_byte_slice_exact(pos).unwrap();
+        input
+    }
+
+    proptest! {
+        #[test]
+        fn single_bytes_are_available_until_the_end((buffer, pos) in buffer_and_position()) {
+            let mut input = input_at(&buffer, pos);
+
+            prop_assert_eq!(input.peek_byte().ok(), buffer.get(pos));
+            prop_assert_eq!(input.read_byte().ok(), buffer.get(pos));
+            prop_assert_eq!(input.position(), usize::min(pos + 1, buffer.len()));
+        }
+
+        #[test]
+        fn exact_slices_succeed_iff_they_fit(
+            (buffer, pos, count) in buffer_and_position().prop_flat_map(|(buffer, pos)| {
+                let remaining = buffer.len() - pos;
+                (Just(buffer), Just(pos), count_for(remaining))
+            })
+        ) {
+            let mut input = input_at(&buffer, pos);
+            let remaining = buffer.len() - pos;
+            let expected = pos.checked_add(count).and_then(|end| buffer.get(pos..end));
+
+            prop_assert_eq!(input.peek_byte_slice_exact(count).ok(), expected);
+            prop_assert_eq!(input.position(), pos);
+
+            match input.read_byte_slice_exact(count) {
+                Ok(slice) => {
+                    prop_assert_eq!(Some(slice), expected);
+                    prop_assert_eq!(input.position(), pos + count);
+                    prop_assert_eq!(input.remaining(), remaining - count);
+                }
+                Err(Error::InsufficientSpace { requested, remaining: reported }) => {
+                    prop_assert!(expected.is_none());
+                    prop_assert_eq!((requested, reported), (count, remaining));
+                    prop_assert_eq!(input.position(), pos);
+                }
+                Err(error) => prop_assert!(false, "unexpected error: {error:?}"),
+            }
+        }
+
+        #[test]
+        fn peeked_slices_are_truncated_at_the_end(
+            (buffer, pos, count) in buffer_and_position().prop_flat_map(|(buffer, pos)| {
+                let remaining = buffer.len() - pos;
+                (Just(buffer), Just(pos), count_for(remaining))
+            })
+        ) {
+            let mut input = input_at(&buffer, pos);
+            let end = pos.saturating_add(count).min(buffer.len());
+
+            prop_assert_eq!(input.peek_byte_slice(count), &buffer[pos..end]);
+            prop_assert_eq!(input.position(), pos);
+        }
+
+        #[test]
+        fn reserving_splits_off_the_following_bytes(
+            (buffer, pos, count) in buffer_and_position().prop_flat_map(|(buffer, pos)| {
+                let remaining = buffer.len() - pos;
+                (Just(buffer), Just(pos), count_for(remaining))
+            })
+        ) {
+            let mut input = input_at(&buffer, pos);
+
+            match pos.checked_add(count).filter(|end| *end <= buffer.len()) {
+                Some(end) => {
+                    let (reserved, mut child) = input.reserve(count).unwrap();
+                    prop_assert_eq!(reserved, &buffer[pos..end]);
+                    prop_assert_eq!(child.remaining(), buffer.len() - end);
+                    prop_assert_eq!(child.peek_byte_slice(usize::MAX), &buffer[end..]);
+                }
+                None => prop_assert!(input.reserve(count).is_err()),
+            }
+            prop_assert_eq!(input.position(), pos);
+        }
+
+        #[test]
+        fn readers_behave_like_slices(
+            (buffer, pos, count) in buffer_and_position().prop_flat_map(|(buffer, pos)| {
+                let remaining = buffer.len() - pos;
+                (Just(buffer), Just(pos), count_for(remaining))
+            }),
+            capacity in 1..16_usize,
+        ) {
+            let mut reader = buffer.as_slice();
+            let storage = ReadBuffer::with_capacity(capacity);
+            let mut input = BufferedInput::from_reader(&mut reader, &storage);
+            input.read_byte_slice_exact(pos).unwrap();
+
+            // Huge counts are only checked against slices, since readers would try to read all of them.
+            let count = usize::min(count, buffer.len() - pos + 2);
+            let expected = buffer.get(pos..pos + count);
+
+            prop_assert_eq!(input.peek_byte().ok(), buffer.get(pos));
+            prop_assert_eq!(input.peek_byte_slice_exact(count).ok(), expected);
+            prop_assert_eq!(input.read_byte_slice_exact(count).ok(), expected);
+            prop_assert_eq!(input.position(), pos + expected.map_or(0, <[u8]>::len));
+        }
+    }
 }
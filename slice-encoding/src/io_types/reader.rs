@@ -0,0 +1,305 @@
+// Copyright (c) ZeroC, Inc.
+
+//! Types for reading a decoder's input incrementally from a [`Read`] implementation, such as a file or a pipe.
+//!
+//! Decoders hand out slices which borrow directly from their input (see
+//! [`TryDecodeBorrowed`](crate::try_decode::TryDecodeBorrowed)), so bytes read from a reader must stay at a fixed
+//! location in memory for as long as the decoder's input lifetime. To achieve this, the bytes are stored in a
+//! [`ReadBuffer`] which is owned by the caller, and which only releases its memory when it's dropped.
+
+use super::{Error, Result};
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::Debug;
+use core::ptr::NonNull;
+use std::io::{ErrorKind, Read};
+
+/// The number of bytes a [`ReadBuffer`] tries to read at once when one isn't explicitly provided.
+pub const DEFAULT_READ_BUFFER_CAPACITY: usize = 8 * 1024;
+
+/// Storage for the bytes which a decoder reads from a [`Read`] implementation.
+///
+/// Bytes are read into fixed-size chunks, which are allocated as needed. Chunks are never moved or freed while the
+/// buffer is alive, so slices into them can safely be handed out for the buffer's entire lifetime. When a value spans
+/// the end of a chunk, its bytes are copied into the start of the next chunk, so that it can still be returned as a
+/// single contiguous slice.
+///
+/// Since decoded values can borrow from any chunk, a buffer holds onto every byte it reads until it's dropped. So its
+/// memory usage isn't bounded by its capacity: it grows with the total number of bytes read, plus the copies of any
+/// values which spanned the end of a chunk. To keep this in check, the chunks allocated while decoding are charged
+/// against the decoder's allocation limit, like any other allocation. When decoding a long-lived stream of separate
+/// messages, use a new buffer for each message to keep memory usage bounded.
+///
+/// # Examples
+///
+/// ```
+/// # use slice_encoding::decoder::Decoder;
+/// # use slice_encoding::io_types::reader::ReadBuffer;
+/// # use slice_encoding::slice2::Slice2;
+/// // Any type that implements `std::io::Read` can be used, such as a file, a socket, or a pipe.
+/// let reader: &[u8] = &[8, b'h', b'i', 1];
+/// let buffer = ReadBuffer::with_capacity(2);
+/// let mut decoder: Decoder<Slice2> = Decoder::from_reader(reader, &buffer);
+///
+/// // Values that span multiple reads are still returned as contiguous slices.
+/// assert_eq!(decoder.try_decode_borrowed::<&str>(), Ok("hi"));
+/// assert_eq!(decoder.try_decode::<bool>(), Ok(true));
+/// ```
+pub struct ReadBuffer {
+    /// The number of bytes this buffer tries to read at once.
+    capacity: usize,
+
+    /// Pointers to each of the chunks this buffer has allocated. Each one was created from a leaked `Box<[u8]>`,
+    /// and is only freed when this buffer is dropped.
+    chunks: RefCell<Vec<NonNull<[u8]>>>,
+}
+
+impl ReadBuffer {
+    /// Creates a new, empty buffer which tries to read [`DEFAULT_READ_BUFFER_CAPACITY`] bytes at once.
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_READ_BUFFER_CAPACITY)
+    }
+
+    /// Creates a new, empty buffer which tries to read `capacity`-many bytes at once.
+    ///
+    /// The capacity is only a hint; chunks are made larger when necessary to hold values that are longer than it.
+    /// A capacity of `0` is treated as `1`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        ReadBuffer {
+            capacity: usize::max(capacity, 1),
+            chunks: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns the number of bytes this buffer tries to read at once.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the total number of bytes held by this buffer, across all of its chunks.
+    pub fn len(&self) -> usize {
+        self.chunks.borrow().iter().map(|chunk| chunk.len()).sum()
+    }
+
+    /// Returns `true` if this buffer hasn't read any bytes yet.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.borrow().is_empty()
+    }
+
+    /// Takes ownership of the provided chunk, and returns a slice over its bytes which lives as long as this buffer.
+    fn store(&self, chunk: Box<[u8]>) -> &[u8] {
+        let chunk = NonNull::from(Box::leak(chunk));
+        self.chunks.borrow_mut().push(chunk);
+
+        // SAFETY: the chunk was just leaked, so this pointer is valid, and nothing else can access its bytes mutably.
+        // Chunks are only freed when this buffer is dropped, which can't happen while the returned slice borrows it.
+        unsafe { chunk.as_ref() }
+    }
+}
+
+impl Default for ReadBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for ReadBuffer {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ReadBuffer")
+            .field("capacity", &self.capacity)
+            .field("chunks", &self.chunks.borrow().len())
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl Drop for ReadBuffer {
+    fn drop(&mut self) {
+        for chunk in self.chunks.get_mut().drain(..) {
+            // SAFETY: each chunk was created by leaking a `Box<[u8]>` in `store`, and is only freed here, once.
+            drop(unsafe { Box::from_raw(chunk.as_ptr()) });
+        }
+    }
+}
+
+/// An input source which reads bytes from a [`Read`] implementation into a [`ReadBuffer`] as they're needed.
+pub struct ReaderSource<'a> {
+    reader: Box<dyn Read + 'a>,
+    storage: &'a ReadBuffer,
+    /// The total number of bytes this source has stored in `storage`.
+    stored: usize,
+    /// The total number of bytes this source is allowed to store in `storage`. See [`Self::set_limit`].
+    limit: usize,
+}
+
+impl<'a> ReaderSource<'a> {
+    /// Creates a new source which reads bytes from `reader`, and stores them in `storage`.
+    pub fn new(reader: impl Read + 'a, storage: &'a ReadBuffer) -> Self {
+        ReaderSource {
+            reader: Box::new(reader),
+            storage,
+            stored: 0,
+            limit: usize::MAX,
+        }
+    }
+
+    /// Returns the total number of bytes this source has stored in its [`ReadBuffer`] so far.
+    pub fn stored(&self) -> usize {
+        self.stored
+    }
+
+    /// Limits the total number of bytes this source can store in its [`ReadBuffer`], including what it's already stored.
+    ///
+    /// Chunks are never grown past this limit, so a malicious size prefix can't trigger an allocation larger than the
+    /// limit before any data arrives, even though the chunk is only charged against a decoder once it's stored.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+
+    /// Reads bytes until there are at least `count` of them available, or the reader reaches end-of-file, and returns
+    /// a slice holding the `unread` bytes, followed by any newly read bytes.
+    ///
+    /// Whatever bytes were read are always returned, even if an error occurs before `count` is reached. If a reader
+    /// returns an error, or storing `count` bytes would exceed this source's limit, it's returned alongside these
+    /// bytes, so the caller doesn't lose them.
+    pub(super) fn fill(&mut self, unread: &'a [u8], count: usize) -> (&'a [u8], Result<()>) {
+        let capacity = self.storage.capacity;
+        let available = self.limit.saturating_sub(self.stored);
+        let mut chunk = Vec::with_capacity(usize::min(usize::max(unread.len(), capacity), available));
+        chunk.extend_from_slice(unread);
+
+        let mut result = Ok(());
+        while chunk.len() < count {
+            // We only grow the chunk by `capacity` bytes at a time, instead of reserving space for `count` bytes
+            // up-front. Otherwise, a malicious size prefix could trigger a huge allocation before any data arrives.
+            // For the same reason, we never grow it past the limit; it'd fail to be charged after it's stored anyways.
+            let filled = chunk.len();
+            let growth = usize::min(capacity, available.saturating_sub(filled));
+            if growth == 0 {
+                result = Err(Error::StorageLimitReached {
+                    limit: self.limit,
+                    stored: self.stored + filled,
+                    requested: count - filled,
+                });
+                break;
+            }
+            chunk.resize(filled + growth, 0);
+
+            match self.reader.read(&mut chunk[filled..]) {
+                Ok(0) => {
+                    chunk.truncate(filled);
+                    break;
+                }
+                Ok(read) => chunk.truncate(filled + read),
+                Err(error) if error.kind() == ErrorKind::Interrupted => chunk.truncate(filled),
+                Err(error) => {
+                    chunk.truncate(filled);
+                    result = Err(Error::Io(error.kind()));
+                    break;
+                }
+            }
+        }
+
+        // If we didn't read anything new, there's no need to store a copy of the unread bytes.
+        match chunk.len() > unread.len() {
+            true => {
+                self.stored += chunk.len();
+                (self.storage.store(chunk.into_boxed_slice()), result)
+            }
+            false => (unread, result),
+        }
+    }
+}
+
+impl Debug for ReaderSource<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ReaderSource").field("storage", &self.storage).finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, feature = "slice2"))]
+mod tests {
+    use super::*;
+    use crate::decoder::{DecodeErrorKind, Decoder};
+    use crate::slice2::Slice2;
+
+    /// A reader which only returns a single byte from each call to `read`, to force values across chunk boundaries.
+    struct TrickleReader<'a>(&'a [u8]);
+
+    impl Read for TrickleReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((byte, rest)), Some(slot)) => {
+                    *slot = *byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn values_spanning_chunks_are_returned_contiguously() {
+        // Two strings: `"hello"` and `"world"`, followed by an `i32`.
+        let payload: &[u8] = &[20, b'h', b'e', b'l', b'l', b'o', 20, b'w', b'o', b'r', b'l', b'd', 7, 0, 0, 0];
+        let buffer = ReadBuffer::with_capacity(3);
+        let mut decoder: Decoder<Slice2> = Decoder::from_reader(TrickleReader(payload), &buffer);
+
+        let first = decoder.try_decode_borrowed::<&str>().unwrap();
+        let second = decoder.try_decode_borrowed::<&str>().unwrap();
+        assert_eq!(decoder.try_decode::<i32>(), Ok(7));
+
+        // Earlier slices are still valid after later reads have allocated new chunks.
+        assert_eq!((first, second), ("hello", "world"));
+        assert_eq!(decoder.position(), payload.len());
+        assert_eq!(decoder.finish(), Ok(()));
+    }
+
+    #[test]
+    fn stored_chunks_are_charged_against_the_allocation_limit() {
+        // A string holding 32 `'a'`s.
+        let mut payload = alloc::vec![128];
+        payload.extend([b'a'; 32]);
+        let buffer = ReadBuffer::with_capacity(8);
+
+        let mut decoder: Decoder<Slice2> = Decoder::from_reader(payload.as_slice(), &buffer).with_allocation_limit(64);
+        assert!(decoder.try_decode_borrowed::<&str>().is_ok());
+        assert_eq!(decoder.remaining_heap_allocation_size(), 64 - buffer.len());
+
+        let mut decoder: Decoder<Slice2> = Decoder::from_reader(payload.as_slice(), &buffer).with_allocation_limit(16);
+        let error = decoder.try_decode_borrowed::<&str>().unwrap_err();
+        assert!(matches!(error.kind(), DecodeErrorKind::HeapAllocationLimitReached { .. }));
+    }
+
+    #[test]
+    fn chunks_are_never_grown_past_the_allocation_limit() {
+        // A string whose length prefix claims it holds 1 MiB, followed by an endless stream of `'a'`s.
+        let reader = [0b0000_0010, 0, 0b0100_0000, 0].chain(std::io::repeat(b'a'));
+        let buffer = ReadBuffer::with_capacity(64);
+        let mut decoder: Decoder<Slice2> = Decoder::from_reader(reader, &buffer).with_allocation_limit(1024);
+
+        let error = decoder.try_decode_borrowed::<&str>().unwrap_err();
+        assert!(matches!(error.kind(), DecodeErrorKind::HeapAllocationLimitReached { .. }));
+        assert!(buffer.len() <= 1024, "stored {} bytes", buffer.len());
+    }
+
+    #[test]
+    fn reader_errors_are_reported() {
+        struct FailingReader;
+
+        impl Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::from(ErrorKind::ConnectionReset))
+            }
+        }
+
+        let buffer = ReadBuffer::new();
+        let mut decoder: Decoder<Slice2> = Decoder::from_reader(FailingReader, &buffer);
+
+        let error = decoder.try_decode::<u8>().unwrap_err();
+        assert_eq!(error.kind(), &DecodeErrorKind::Io { kind: ErrorKind::ConnectionReset });
+    }
+}
@@ -0,0 +1,308 @@
+// Copyright (c) ZeroC, Inc.
+
+//! Iterators which decode the elements of a collection one at a time, instead of all at once.
+//!
+//! Decoding a collection with [`TryDecode`] or [`TryDecodeCollection`](crate::try_decode::TryDecodeCollection)
+//! materializes all of its elements before returning. The types in this module instead decode the collection's length
+//! up-front, then decode one element each time they're advanced. This lets callers stop early, or stream elements
+//! directly into their own storage.
+//!
+//! While one of these iterators exists, it mutably borrows the decoder. If it's dropped before reaching the end of
+//! its collection, the decoder is left positioned in the middle of the collection. Use `finish` to skip past any
+//! elements that haven't been decoded yet.
+
+use crate::decoder::{DecodeContext, DecodeResult, Decoder};
+use crate::try_decode::{DecodeFn, TryDecode};
+use crate::Encoding;
+
+use core::iter::FusedIterator;
+
+/// An iterator which lazily decodes the elements of a Slice sequence.
+///
+/// The lifetime `'a` is the lifetime of the decoder's input, and `'b` is the lifetime of the borrow of the decoder.
+///
+/// # Examples
+///
+#[cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+#[cfg_attr(feature = "alloc", doc = "```")]
+/// # use slice_encoding::decoder::Decoder;
+/// # use slice_encoding::lazy::LazySequence;
+/// # use slice_encoding::slice2::Slice2;
+/// // A sequence of 3 strings: `["a", "bc", "def"]`, followed by a bool.
+/// let buffer: &[u8] = &[12, 4, b'a', 8, b'b', b'c', 12, b'd', b'e', b'f', 1];
+/// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+///
+/// let mut strings = LazySequence::<_, String>::new(&mut decoder).unwrap();
+/// assert_eq!(strings.remaining(), 3);
+///
+/// // We only care about the first string, so we skip the rest.
+/// assert_eq!(strings.next(), Some(Ok("a".to_owned())));
+/// strings.finish().unwrap();
+///
+/// assert_eq!(decoder.try_decode::<bool>(), Ok(true));
+/// ```
+#[derive(Debug)]
+pub struct LazySequence<'a, 'b, E: Encoding, T> {
+    decoder: &'b mut Decoder<'a, E>,
+    decode_fn: DecodeFn<T, E>,
+    length: usize,
+    index: usize,
+    failed: bool,
+}
+
+impl<'a, 'b, E: Encoding, T> LazySequence<'a, 'b, E, T> {
+    /// Decodes the length of a sequence from the provided decoder, and returns an iterator over its elements, which
+    /// decodes each element with `T`'s [`try_decode`](TryDecode::try_decode) function.
+    pub fn new(decoder: &'b mut Decoder<'a, E>) -> DecodeResult<Self>
+        where T: TryDecode<E>,
+    {
+        let length = decoder.try_decode_collection_length(T::MIN_ENCODED_SIZE)?;
+        Ok(Self::from_parts(decoder, T::try_decode, length))
+    }
+
+    /// Decodes the length of a sequence from the provided decoder, and returns an iterator over its elements, which
+    /// decodes each element with the provided `decode_fn` function.
+    pub fn with_fn(decoder: &'b mut Decoder<'a, E>, decode_fn: DecodeFn<T, E>) -> DecodeResult<Self> {
+        // We can't know how many bytes `decode_fn` reads, so we can't assume the elements take up any space.
+        let length = decoder.try_decode_collection_length(0)?;
+        Ok(Self::from_parts(decoder, decode_fn, length))
+    }
+
+    fn from_parts(decoder: &'b mut Decoder<'a, E>, decode_fn: DecodeFn<T, E>, length: usize) -> Self {
+        LazySequence { decoder, decode_fn, length, index: 0, failed: false }
+    }
+
+    /// Returns the total number of elements in this sequence, including any that have already been decoded.
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Returns `true` if this sequence has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Returns the number of elements that haven't been decoded yet.
+    pub fn remaining(&self) -> usize {
+        self.length - self.index
+    }
+
+    /// Decodes and discards any elements that haven't been decoded yet, leaving the decoder positioned after the end
+    /// of this sequence.
+    ///
+    /// If any of those elements fail to decode, the first error is returned.
+    pub fn finish(mut self) -> DecodeResult<()> {
+        self.try_for_each(|result| result.map(|_| ()))
+    }
+}
+
+impl<E: Encoding, T> Iterator for LazySequence<'_, '_, E, T> {
+    type Item = DecodeResult<T>;
+
+    /// Decodes and returns the next element of this sequence.
+    ///
+    /// If decoding an element fails, the error is returned, and this iterator returns `None` from then on, since the
+    /// decoder's position is no longer meaningful.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.index >= self.length {
+            return None;
+        }
+
+        // Each element is nested inside the sequence, just like when the whole sequence is decoded at once.
+        let decode_fn = self.decode_fn;
+        let result = self.decoder.nested(|decoder| decode_fn(decoder)).at_index(self.index);
+        self.index += 1;
+        self.failed = result.is_err();
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.failed {
+            true => (0, Some(0)),
+            false => (0, Some(self.remaining())),
+        }
+    }
+}
+
+impl<E: Encoding, T> FusedIterator for LazySequence<'_, '_, E, T> {}
+
+/// An iterator which lazily decodes the entries of a Slice dictionary.
+///
+/// The lifetime `'a` is the lifetime of the decoder's input, and `'b` is the lifetime of the borrow of the decoder.
+///
+/// # Examples
+///
+/// ```
+/// # use slice_encoding::decoder::Decoder;
+/// # use slice_encoding::lazy::LazyDictionary;
+/// # use slice_encoding::slice2::Slice2;
+/// // A dictionary with 2 entries: `{1: true, 2: false}`.
+/// let buffer: &[u8] = &[8, 1, 1, 2, 0];
+/// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+///
+/// let entries = LazyDictionary::<_, u8, bool>::new(&mut decoder).unwrap();
+/// let true_keys: Vec<u8> = entries
+///     .filter_map(|entry| entry.map(|(key, value)| value.then_some(key)).transpose())
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+///
+/// assert_eq!(true_keys, [1]);
+/// ```
+#[derive(Debug)]
+pub struct LazyDictionary<'a, 'b, E: Encoding, K, V> {
+    entries: LazySequence<'a, 'b, E, (K, V)>,
+}
+
+impl<'a, 'b, E: Encoding, K, V> LazyDictionary<'a, 'b, E, K, V> {
+    /// Decodes the length of a dictionary from the provided decoder, and returns an iterator over its entries, which
+    /// decodes each entry's key and value with their [`try_decode`](TryDecode::try_decode) functions.
+    pub fn new(decoder: &'b mut Decoder<'a, E>) -> DecodeResult<Self>
+    where
+        K: TryDecode<E>,
+        V: TryDecode<E>,
+    {
+        let decode_fn = |decoder: &mut Decoder<E>| {
+            let key = K::try_decode(decoder)?;
+            let value = V::try_decode(decoder)?;
+            Ok((key, value))
+        };
+        let min_entry_size = K::MIN_ENCODED_SIZE.saturating_add(V::MIN_ENCODED_SIZE);
+
+        let length = decoder.try_decode_collection_length(min_entry_size)?;
+        let entries = LazySequence::from_parts(decoder, decode_fn, length);
+        Ok(LazyDictionary { entries })
+    }
+
+    /// Decodes the length of a dictionary from the provided decoder, and returns an iterator over its entries, which
+    /// decodes each entry with the provided `decode_fn` function.
+    pub fn with_fn(decoder: &'b mut Decoder<'a, E>, decode_fn: DecodeFn<(K, V), E>) -> DecodeResult<Self> {
+        let entries = LazySequence::with_fn(decoder, decode_fn)?;
+        Ok(LazyDictionary { entries })
+    }
+
+    /// Returns the total number of entries in this dictionary, including any that have already been decoded.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if this dictionary has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the number of entries that haven't been decoded yet.
+    pub fn remaining(&self) -> usize {
+        self.entries.remaining()
+    }
+
+    /// Decodes and discards any entries that haven't been decoded yet, leaving the decoder positioned after the end
+    /// of this dictionary.
+    ///
+    /// If any of those entries fail to decode, the first error is returned.
+    pub fn finish(self) -> DecodeResult<()> {
+        self.entries.finish()
+    }
+}
+
+impl<E: Encoding, K, V> Iterator for LazyDictionary<'_, '_, E, K, V> {
+    type Item = DecodeResult<(K, V)>;
+
+    /// Decodes and returns the next entry of this dictionary.
+    ///
+    /// If decoding an entry fails, the error is returned, and this iterator returns `None` from then on, since the
+    /// decoder's position is no longer meaningful.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<E: Encoding, K, V> FusedIterator for LazyDictionary<'_, '_, E, K, V> {}
+
+#[cfg(all(test, feature = "slice2"))]
+mod tests {
+    use super::*;
+    use crate::decoder::DecodeErrorKind;
+    use crate::slice2::Slice2;
+
+    #[test]
+    fn sequences_decode_one_element_at_a_time() {
+        // A sequence of 3 `u8`s: `[1, 2, 3]`, followed by a bool.
+        let buffer: &[u8] = &[12, 1, 2, 3, 1];
+        let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+
+        let mut elements = LazySequence::<_, u8>::new(&mut decoder).unwrap();
+        assert_eq!((elements.len(), elements.remaining()), (3, 3));
+        assert_eq!(elements.next(), Some(Ok(1)));
+        assert_eq!(elements.size_hint(), (0, Some(2)));
+        assert!(elements.by_ref().eq([Ok(2), Ok(3)]));
+        assert_eq!(elements.next(), None);
+
+        assert_eq!(decoder.try_decode::<bool>(), Ok(true));
+    }
+
+    #[test]
+    fn iterators_stop_after_the_first_error() {
+        // A sequence of 3 bools, where the second one is invalid.
+        let buffer: &[u8] = &[12, 1, 2, 0];
+        let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+
+        let mut elements = LazySequence::<_, bool>::new(&mut decoder).unwrap();
+        assert_eq!(elements.next(), Some(Ok(true)));
+
+        let error = elements.next().unwrap().unwrap_err();
+        assert!(matches!(error.kind(), DecodeErrorKind::IllegalValue { value: 2, .. }));
+        assert_eq!(error.offset(), 2);
+        #[cfg(feature = "alloc")]
+        assert!(error.path().eq(&[crate::decoder::PathSegment::Index(1)]));
+
+        assert_eq!(elements.next(), None);
+        assert_eq!(elements.size_hint(), (0, Some(0)));
+    }
+
+    #[test]
+    fn elements_are_nested_inside_their_collection() {
+        // A sequence holding a single `u8`, and a dictionary holding a single entry: `{1: true}`.
+        let sequence: &[u8] = &[4, 7];
+        let dictionary: &[u8] = &[4, 1, 1];
+
+        // Decoding the elements of a collection takes one level of nesting, like decoding the whole collection does.
+        let mut decoder: Decoder<Slice2> = Decoder::new(sequence).with_max_depth(0);
+        let error = LazySequence::<_, u8>::new(&mut decoder).unwrap().next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), &DecodeErrorKind::DepthLimitExceeded { limit: 0 });
+
+        let mut decoder: Decoder<Slice2> = Decoder::new(dictionary).with_max_depth(0);
+        let error = LazyDictionary::<_, u8, bool>::new(&mut decoder).unwrap().next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), &DecodeErrorKind::DepthLimitExceeded { limit: 0 });
+
+        // Every element exits its nesting level once it's decoded, so the next collection can use the same level.
+        let buffer: &[u8] = &[4, 1, 1, 4, 7];
+        let mut decoder: Decoder<Slice2> = Decoder::new(buffer).with_max_depth(1);
+        let entries = LazyDictionary::<_, u8, bool>::new(&mut decoder).unwrap();
+        assert!(entries.eq([Ok((1, true))]));
+        let elements = LazySequence::<_, u8>::new(&mut decoder).unwrap();
+        assert!(elements.eq([Ok(7)]));
+    }
+
+    #[test]
+    fn partially_consumed_iterators_can_be_finished() {
+        // A dictionary with 3 entries: `{1: true, 2: false, 3: true}`, followed by a `u8`.
+        let buffer: &[u8] = &[12, 1, 1, 2, 0, 3, 1, 9];
+
+        let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+        let mut entries = LazyDictionary::<_, u8, bool>::new(&mut decoder).unwrap();
+        assert_eq!(entries.next(), Some(Ok((1, true))));
+        assert_eq!(entries.remaining(), 2);
+        entries.finish().unwrap();
+        assert_eq!(decoder.try_decode::<u8>(), Ok(9));
+
+        // Dropping an iterator without finishing it leaves the decoder in the middle of the collection.
+        let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+        let first = LazyDictionary::<_, u8, bool>::new(&mut decoder).unwrap().next();
+        assert_eq!(first, Some(Ok((1, true))));
+        assert_eq!(decoder.position(), 3);
+    }
+}
@@ -21,15 +21,23 @@ pub mod slice2;
 #[cfg(feature = "slice1")]
 pub mod slice1;
 
+// The allocation tracker types are only needed if decoders can allocate memory.
+#[cfg(feature = "alloc")]
+pub mod allocation_tracker;
+
 pub mod decoder;
 pub mod encoder;
 pub mod io_types;
+pub mod lazy;
+pub mod slice_view;
 pub mod try_decode;
 pub mod try_encode;
+pub mod try_skip;
 
 // These modules are private because they don't export any types, just implementations.
 mod decoding;
 mod encoding;
+mod skipping;
 
 use decoder::{Decoder, DecodeResult};
 use encoder::{Encoder, EncodeResult};
@@ -0,0 +1,231 @@
+// Copyright (c) ZeroC, Inc.
+
+use crate::decoder::{DecodeContext, DecodeResult, Decoder};
+use crate::slice_view::{SliceView, ViewElement};
+use crate::try_skip::TrySkip;
+use crate::Encoding;
+
+#[cfg(feature = "alloc")]
+use alloc::borrow::Cow;
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
+
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+
+// =============================================================================
+// Fixed-length type implementations
+// =============================================================================
+
+/// This macro is for implementing `TrySkip` on types with a fixed-size encoding, which are skipped by advancing the
+/// decoder by that many bytes.
+macro_rules! implement_slice_skippable_for_fixed_size_type {
+    ($ty:ty, $size:expr, $encoding:ident$(: $($bounds:tt)+)?) => {
+        impl$(<$encoding: $($bounds)+>)? TrySkip<$encoding> for $ty {
+            const FIXED_SIZE: Option<usize> = Some($size);
+
+            #[doc = concat!("Skips the ", stringify!($size), " bytes that a `", stringify!($ty), "` is encoded on.")]
+            fn try_skip(decoder: &mut Decoder<$encoding>) -> DecodeResult<()> {
+                decoder.read_bytes_exact($size).map(|_| ())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "slice2")]
+pub(crate) use implement_slice_skippable_for_fixed_size_type;
+
+implement_slice_skippable_for_fixed_size_type!(bool, 1, E: Encoding);
+implement_slice_skippable_for_fixed_size_type!(u8, 1, E: Encoding);
+implement_slice_skippable_for_fixed_size_type!(i16, 2, E: Encoding);
+implement_slice_skippable_for_fixed_size_type!(i32, 4, E: Encoding);
+implement_slice_skippable_for_fixed_size_type!(i64, 8, E: Encoding);
+implement_slice_skippable_for_fixed_size_type!(f32, 4, E: Encoding);
+implement_slice_skippable_for_fixed_size_type!(f64, 8, E: Encoding);
+
+// =============================================================================
+// Sequence type implementations
+// =============================================================================
+
+impl<E: Encoding> TrySkip<E> for str {
+    /// Skips a string by decoding its length, then skipping that many bytes.
+    fn try_skip(decoder: &mut Decoder<E>) -> DecodeResult<()> {
+        <[u8]>::try_skip(decoder)
+    }
+}
+
+impl<E: Encoding, T> TrySkip<E> for [T]
+    where T: TrySkip<E>,
+{
+    /// Skips a sequence by decoding its length, then skipping each of its elements.
+    ///
+    /// If the element type has a fixed size, all the elements are skipped at once.
+    fn try_skip(decoder: &mut Decoder<E>) -> DecodeResult<()> {
+        decoder.nested(|decoder| {
+            let offset = decoder.position();
+            let length = decoder.try_decode_collection_length(T::FIXED_SIZE.unwrap_or(0))?;
+
+            match T::FIXED_SIZE {
+                Some(size) => {
+                    let byte_size = Decoder::<E>::collection_byte_size(length, size, offset)?;
+                    decoder.read_bytes_exact(byte_size).map(|_| ())
+                }
+                None => (0..length).try_for_each(|i| T::try_skip(decoder).at_index(i)),
+            }
+        })
+    }
+}
+
+impl<E: Encoding, T: ?Sized + TrySkip<E>> TrySkip<E> for &T {
+    const FIXED_SIZE: Option<usize> = T::FIXED_SIZE;
+
+    /// Delegates to the implementation for `T`.
+    #[inline(always)]
+    fn try_skip(decoder: &mut Decoder<E>) -> DecodeResult<()> {
+        T::try_skip(decoder)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<E: Encoding> TrySkip<E> for String {
+    /// Delegates to the implementation for `str`.
+    #[inline(always)]
+    fn try_skip(decoder: &mut Decoder<E>) -> DecodeResult<()> {
+        str::try_skip(decoder)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<E: Encoding, T> TrySkip<E> for Vec<T>
+    where T: TrySkip<E>,
+{
+    /// Delegates to the implementation for `[T]`.
+    #[inline(always)]
+    fn try_skip(decoder: &mut Decoder<E>) -> DecodeResult<()> {
+        <[T]>::try_skip(decoder)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<E: Encoding, T> TrySkip<E> for Cow<'_, T>
+    where T: ?Sized + alloc::borrow::ToOwned + TrySkip<E>,
+{
+    /// Delegates to the implementation for `T`.
+    #[inline(always)]
+    fn try_skip(decoder: &mut Decoder<E>) -> DecodeResult<()> {
+        T::try_skip(decoder)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<E: Encoding> TrySkip<E> for Bytes {
+    /// Delegates to the implementation for `[u8]`.
+    #[inline(always)]
+    fn try_skip(decoder: &mut Decoder<E>) -> DecodeResult<()> {
+        <[u8]>::try_skip(decoder)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Encoding, T> TrySkip<E> for HashSet<T>
+    where T: TrySkip<E>,
+{
+    /// Delegates to the implementation for `[T]`, since sets are encoded as sequences.
+    #[inline(always)]
+    fn try_skip(decoder: &mut Decoder<E>) -> DecodeResult<()> {
+        <[T]>::try_skip(decoder)
+    }
+}
+
+impl<E: Encoding, T> TrySkip<E> for SliceView<'_, T>
+    where T: ViewElement + TrySkip<E>,
+{
+    /// Delegates to the implementation for `[T]`.
+    #[inline(always)]
+    fn try_skip(decoder: &mut Decoder<E>) -> DecodeResult<()> {
+        <[T]>::try_skip(decoder)
+    }
+}
+
+// =============================================================================
+// Pointer type implementations
+// =============================================================================
+
+#[cfg(feature = "alloc")]
+impl<E: Encoding, T> TrySkip<E> for Box<T>
+    where T: ?Sized + TrySkip<E>,
+{
+    const FIXED_SIZE: Option<usize> = T::FIXED_SIZE;
+
+    /// Delegates to the implementation for `T`, since boxing a value doesn't affect its encoding.
+    #[inline(always)]
+    fn try_skip(decoder: &mut Decoder<E>) -> DecodeResult<()> {
+        decoder.nested(T::try_skip)
+    }
+}
+
+// =============================================================================
+// Dictionary type implementations
+// =============================================================================
+
+/// Skips a dictionary by decoding its length, then skipping the key and value of each of its entries.
+#[cfg(feature = "alloc")]
+fn try_skip_dictionary<E: Encoding, K, V>(decoder: &mut Decoder<E>) -> DecodeResult<()>
+where
+    K: TrySkip<E>,
+    V: TrySkip<E>,
+{
+    decoder.nested(|decoder| {
+        let (min_entry_size, fixed_entry_size) = match (K::FIXED_SIZE, V::FIXED_SIZE) {
+            (Some(key_size), Some(value_size)) => {
+                let entry_size = key_size.saturating_add(value_size);
+                (entry_size, Some(entry_size))
+            }
+            _ => (0, None),
+        };
+        let offset = decoder.position();
+        let length = decoder.try_decode_collection_length(min_entry_size)?;
+
+        match fixed_entry_size {
+            Some(size) => {
+                let byte_size = Decoder::<E>::collection_byte_size(length, size, offset)?;
+                decoder.read_bytes_exact(byte_size).map(|_| ())
+            }
+            None => (0..length).try_for_each(|i| {
+                K::try_skip(decoder).at_index(i)?;
+                V::try_skip(decoder).at_index(i)
+            }),
+        }
+    })
+}
+
+#[cfg(feature = "std")]
+impl<E: Encoding, K, V> TrySkip<E> for HashMap<K, V>
+where
+    K: TrySkip<E>,
+    V: TrySkip<E>,
+{
+    fn try_skip(decoder: &mut Decoder<E>) -> DecodeResult<()> {
+        try_skip_dictionary::<E, K, V>(decoder)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<E: Encoding, K, V> TrySkip<E> for BTreeMap<K, V>
+where
+    K: TrySkip<E>,
+    V: TrySkip<E>,
+{
+    fn try_skip(decoder: &mut Decoder<E>) -> DecodeResult<()> {
+        try_skip_dictionary::<E, K, V>(decoder)
+    }
+}
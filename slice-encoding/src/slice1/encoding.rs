@@ -1 +1,78 @@
 // Copyright (c) ZeroC, Inc.
+
+use super::Slice1;
+use crate::encoder::{EncodeError, EncodeResult, Encoder};
+use crate::try_encode::TryEncode;
+
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+// =============================================================================
+// Unsupported type implementations
+// =============================================================================
+
+// Sequences and dictionaries with optional elements can only be encoded with Slice2. They implement `TryEncode` for
+// Slice1 anyways, so that generic code can be written over both encodings; but encoding them always fails.
+
+impl<T> TryEncode<Slice1> for &[Option<T>] {
+    /// Always returns [`UnsupportedType`](EncodeError::UnsupportedType), since Slice1 has no encoding for sequences of
+    /// optional elements.
+    fn try_encode(self, _: &mut Encoder<Slice1>) -> EncodeResult<()> {
+        Err(EncodeError::UnsupportedType {
+            typename: "sequence of optionals",
+            desc: "sequences with optional elements can only be encoded with Slice2",
+        })
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> TryEncode<Slice1> for &Vec<Option<T>> {
+    /// Delegates to the implementation for `&[Option<T>]`.
+    #[inline(always)]
+    fn try_encode(self, encoder: &mut Encoder<Slice1>) -> EncodeResult<()> {
+        self.as_slice().try_encode(encoder)
+    }
+}
+
+/// This macro implements `TryEncode<Slice1>` for dictionary types with optional values, which always fails.
+macro_rules! implement_unsupported_optional_dictionary_type {
+    ($ty:ident, $feature:literal) => {
+        #[cfg(feature = $feature)]
+        impl<K, V> TryEncode<Slice1> for &$ty<K, Option<V>> {
+            /// Always returns [`UnsupportedType`](EncodeError::UnsupportedType), since Slice1 has no encoding for
+            /// dictionaries with optional values.
+            fn try_encode(self, _: &mut Encoder<Slice1>) -> EncodeResult<()> {
+                Err(EncodeError::UnsupportedType {
+                    typename: "dictionary with optional values",
+                    desc: "dictionaries with optional values can only be encoded with Slice2",
+                })
+            }
+        }
+    };
+}
+
+implement_unsupported_optional_dictionary_type!(HashMap, "std");
+implement_unsupported_optional_dictionary_type!(BTreeMap, "alloc");
+
+#[cfg(test)]
+mod tests {
+    use crate::encoder::{EncodeError, Encoder};
+    use crate::slice1::Slice1;
+
+    #[test]
+    fn encoding_optionals_with_slice1_is_unsupported() {
+        let values = [Some(1_u8), None];
+
+        let mut buffer = [0; 8];
+        let mut encoder: Encoder<Slice1> = Encoder::from_slice(&mut buffer);
+        let error = encoder.try_encode(values.as_slice()).unwrap_err();
+
+        assert!(matches!(error, EncodeError::UnsupportedType { .. }));
+        assert_eq!(encoder.position(), 0);
+    }
+}
@@ -4,8 +4,10 @@
 mod decoding;
 mod encoding;
 
-use crate::decoder::{Decoder, DecodeResult};
-use crate::encoder::{Encoder, EncodeResult};
+use crate::decoder::{DecodeError, DecodeErrorKind, Decoder, DecodeResult};
+use crate::encoder::{EncodeError, Encoder, EncodeResult};
+use crate::try_decode::TryDecode;
+use crate::try_encode::TryEncode;
 use crate::Encoding;
 
 /// TODO
@@ -13,13 +15,80 @@ use crate::Encoding;
 pub struct Slice1;
 
 impl Encoding for Slice1 {
-    /// TODO
+    /// Decodes a size that was encoded with the Slice1 encoding.
+    ///
+    /// Sizes less than `255` are encoded on a single byte. Larger sizes are encoded as a byte with the value `255`,
+    /// followed by the size itself, encoded as an `int32`.
+    ///
+    /// Since there is exactly one valid encoding for each size, this returns `Err` for sizes that were encoded with
+    /// the 5 byte form but are less than `255`, and for negative sizes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice1::Slice1;
+    /// # use slice_encoding::Encoding;
+    /// let mut decoder: Decoder<Slice1> = Decoder::new(&[254]);
+    /// assert_eq!(Slice1::try_decode_size(&mut decoder), Ok(254));
+    ///
+    /// let mut decoder: Decoder<Slice1> = Decoder::new(&[255, 0, 1, 0, 0]);
+    /// assert_eq!(Slice1::try_decode_size(&mut decoder), Ok(256));
+    ///
+    /// // `7` must be encoded on a single byte.
+    /// let mut decoder: Decoder<Slice1> = Decoder::new(&[255, 7, 0, 0, 0]);
+    /// assert!(Slice1::try_decode_size(&mut decoder).is_err());
+    ///
+    /// // Sizes cannot be negative.
+    /// let mut decoder: Decoder<Slice1> = Decoder::new(&[255, 255, 255, 255, 255]);
+    /// assert!(Slice1::try_decode_size(&mut decoder).is_err());
+    /// ```
     fn try_decode_size(decoder: &mut Decoder<Slice1>) -> DecodeResult<usize> {
-        todo!() // TODO
+        let offset = decoder.position();
+        let byte = *decoder.read_byte()?;
+        if byte < 255 {
+            return Ok(byte as usize);
+        }
+
+        let size = i32::try_decode(decoder)?;
+        let desc = match size {
+            ..=-1 => "sizes cannot be negative",
+            0..=254 => "sizes less than 255 must be encoded on a single byte",
+            // SAFETY: `size` is positive, and the Slice encoding isn't supported on platforms with 16 bit pointers.
+            _ => return Ok(size as usize),
+        };
+        let kind = DecodeErrorKind::IllegalValue { value: size as i128, desc };
+        Err(DecodeError::new(kind, offset))
     }
 
-    /// TODO
+    /// Encodes a size with the Slice1 encoding.
+    ///
+    /// Sizes less than `255` are encoded on a single byte. Larger sizes are encoded as a byte with the value `255`,
+    /// followed by the size itself, encoded as an `int32`. If the size is larger than [`i32::MAX`], this returns
+    /// [`CollectionTooLarge`](EncodeError::CollectionTooLarge) instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice1::Slice1;
+    /// # use slice_encoding::Encoding;
+    /// let mut buffer = [0; 16];
+    /// let mut encoder: Encoder<Slice1> = Encoder::from_slice(&mut buffer);
+    /// Slice1::try_encode_size(254, &mut encoder).unwrap();
+    /// Slice1::try_encode_size(256, &mut encoder).unwrap();
+    ///
+    /// assert_eq!(encoder.as_bytes(), &[254, 255, 0, 1, 0, 0]);
+    /// ```
     fn try_encode_size(size: usize, encoder: &mut Encoder<Slice1>) -> EncodeResult<()> {
-        todo!() // TODO
+        if size < 255 {
+            return encoder.write_byte(size as u8);
+        }
+
+        let Ok(size) = i32::try_from(size) else {
+            return Err(EncodeError::CollectionTooLarge { length: size, max: i32::MAX as u64 });
+        };
+        encoder.write_byte(255)?;
+        size.try_encode(encoder)
     }
 }
@@ -0,0 +1,197 @@
+// Copyright (c) ZeroC, Inc.
+
+//! Functions for reading and writing length-prefixed Slice2 frames with `tokio`'s [`AsyncRead`] and [`AsyncWrite`].
+//!
+//! A frame is a payload's encoded bytes, preceded by their length, encoded as a `varuint62`. Frames are read into
+//! memory in their entirety before being decoded, so that decoding itself can stay synchronous.
+
+use super::{Slice2, Slice2Decoder, Slice2Encoder};
+use crate::allocation_tracker::AllocationTracker;
+use crate::decoder::{DecodeError, DecodeErrorKind, DecodeResult};
+use crate::encoder::Encoder;
+use crate::Encoding;
+
+use alloc::vec::Vec;
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The maximum number of bytes a `varuint62` can be encoded on.
+const MAX_LENGTH_PREFIX_SIZE: usize = 8;
+
+impl<'a> Slice2Decoder<'a> {
+    /// Reads a length-prefixed frame from the provided reader into `buffer`, and returns a decoder over it.
+    ///
+    /// Any space `buffer` needs to grow by to hold the frame is charged against `tracker` before it's allocated, so a
+    /// malicious length can't trigger a huge allocation. Since `buffer` keeps its capacity, reusing it for each frame
+    /// means its memory is only charged once, instead of once per frame. The returned decoder uses `tracker` as its
+    /// allocation tracker, so the frame and everything decoded from it share the same limit. Any existing contents of
+    /// `buffer` are overwritten.
+    ///
+    /// If the reader is already at end-of-file (for example, because the peer closed the connection in between
+    /// frames), this returns `Ok(None)`. Reaching end-of-file part way through a frame returns an error instead.
+    ///
+    /// Note that the offsets in errors returned by the decoder are relative to the start of the frame's payload, not
+    /// the start of its length prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::allocation_tracker::{AllocationTracker, LocalAllocationTracker};
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// // A frame holding 2 bytes: a bool and a `u8`.
+    /// let mut reader: &[u8] = &[8, 1, 7];
+    /// let tracker = LocalAllocationTracker::new(1024);
+    /// let mut buffer = Vec::new();
+    ///
+    /// let frame = Decoder::<Slice2>::from_async_read(&mut reader, &mut buffer, &tracker).await.unwrap();
+    /// let mut decoder = frame.expect("the reader wasn't at end-of-file");
+    /// assert_eq!(decoder.try_decode::<bool>(), Ok(true));
+    /// assert_eq!(decoder.try_decode::<u8>(), Ok(7));
+    /// assert_eq!(tracker.total(), 2);
+    /// # }
+    /// ```
+    pub async fn from_async_read<R>(
+        reader: &mut R,
+        buffer: &'a mut Vec<u8>,
+        tracker: &'a dyn AllocationTracker,
+    ) -> DecodeResult<Option<Self>>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        // The first byte of the length prefix tells us how many more bytes it's encoded on.
+        let mut prefix = [0; MAX_LENGTH_PREFIX_SIZE];
+        if reader.read(&mut prefix[..1]).await.map_err(|error| io_error(error, 0))? == 0 {
+            return Ok(None);
+        }
+        let prefix_size = 1 << (prefix[0] & 0b11);
+        let prefix = &mut prefix[..prefix_size];
+        reader.read_exact(&mut prefix[1..]).await.map_err(|error| io_error(error, 1))?;
+
+        let length = Slice2::try_decode_size(&mut Slice2Decoder::new(&*prefix))?;
+        let growth = length.saturating_sub(buffer.capacity());
+        tracker.try_increase_total(growth).map_err(|kind| DecodeError::new(kind, 0))?;
+
+        buffer.clear();
+        buffer.reserve_exact(length);
+        buffer.resize(length, 0);
+        reader.read_exact(buffer).await.map_err(|error| io_error(error, prefix_size))?;
+
+        Ok(Some(Slice2Decoder::new(buffer).with_allocation_tracker(tracker)))
+    }
+}
+
+impl Slice2Encoder<'_> {
+    /// Writes the bytes that have been written into this encoder so far to the provided writer as a length-prefixed
+    /// frame, then flushes the writer. The frame can be read back with [`Decoder::from_async_read`].
+    ///
+    /// This encoder's bytes are left untouched, so it can be flushed to other writers as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let mut encoder: Encoder<Slice2> = Encoder::new();
+    /// encoder.try_encode(true).unwrap();
+    /// encoder.try_encode(7_u8).unwrap();
+    ///
+    /// let mut writer = Vec::new();
+    /// encoder.flush_to_async_write(&mut writer).await.unwrap();
+    /// assert_eq!(writer, [8, 1, 7]);
+    /// # }
+    /// ```
+    ///
+    /// [`Decoder::from_async_read`]: crate::decoder::Decoder::from_async_read
+    pub async fn flush_to_async_write<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        let payload = self.as_bytes();
+
+        let mut prefix = [0; MAX_LENGTH_PREFIX_SIZE];
+        let mut prefix_encoder: Slice2Encoder = Encoder::from_slice(&mut prefix);
+        Slice2::try_encode_size(payload.len(), &mut prefix_encoder)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+        writer.write_all(prefix_encoder.as_bytes()).await?;
+        writer.write_all(payload).await?;
+        writer.flush().await
+    }
+}
+
+/// Converts an error returned by a reader into a [`DecodeError`] at the provided offset into the frame.
+fn io_error(error: io::Error, offset: usize) -> DecodeError {
+    let kind = match error.kind() {
+        io::ErrorKind::UnexpectedEof => DecodeErrorKind::EndOfBuffer,
+        kind => DecodeErrorKind::Io { kind },
+    };
+    DecodeError::new(kind, offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allocation_tracker::LocalAllocationTracker;
+    use crate::decoder::Decoder;
+    use alloc::string::String;
+
+    #[tokio::test]
+    async fn frames_round_trip_through_async_io() {
+        let mut writer = Vec::new();
+        for message in ["first", "second"] {
+            let mut encoder: Encoder<Slice2> = Encoder::new();
+            encoder.try_encode(message).unwrap();
+            encoder.flush_to_async_write(&mut writer).await.unwrap();
+        }
+
+        let mut reader = writer.as_slice();
+        let tracker = LocalAllocationTracker::unlimited();
+        let mut buffer = Vec::new();
+        let mut messages = Vec::new();
+        while let Some(mut decoder) = Decoder::from_async_read(&mut reader, &mut buffer, &tracker).await.unwrap() {
+            messages.push(decoder.try_decode::<String>().unwrap());
+        }
+
+        assert_eq!(messages, ["first", "second"]);
+    }
+
+    #[tokio::test]
+    async fn reused_buffers_are_only_charged_for_growing() {
+        // Two frames, holding 2 bytes and 1 byte.
+        let mut reader: &[u8] = &[8, 1, 2, 4, 3];
+        let tracker = LocalAllocationTracker::new(1024);
+        let mut buffer = Vec::new();
+
+        Decoder::from_async_read(&mut reader, &mut buffer, &tracker).await.unwrap();
+        Decoder::from_async_read(&mut reader, &mut buffer, &tracker).await.unwrap();
+        assert_eq!(tracker.total(), 2);
+    }
+
+    #[tokio::test]
+    async fn frames_larger_than_the_allocation_limit_are_rejected_before_reading_them() {
+        // A frame which claims to hold `2^40` bytes, but doesn't hold any.
+        let mut reader: &[u8] = &[0b0000_0011, 0, 0, 0, 0, 0b0000_0100, 0, 0];
+        let tracker = LocalAllocationTracker::new(1024);
+        let mut buffer = Vec::new();
+
+        let error = Decoder::from_async_read(&mut reader, &mut buffer, &tracker).await.unwrap_err();
+        assert!(matches!(error.kind(), DecodeErrorKind::HeapAllocationLimitReached { .. }));
+        assert_eq!(buffer.capacity(), 0);
+    }
+
+    #[tokio::test]
+    async fn truncated_frames_are_reported_as_reaching_end_of_buffer() {
+        // A frame which claims to hold 3 bytes, but only holds 2.
+        let mut reader: &[u8] = &[12, 1, 2];
+        let tracker = LocalAllocationTracker::unlimited();
+        let mut buffer = Vec::new();
+
+        let error = Decoder::from_async_read(&mut reader, &mut buffer, &tracker).await.unwrap_err();
+        assert_eq!(error.kind(), &DecodeErrorKind::EndOfBuffer);
+    }
+}
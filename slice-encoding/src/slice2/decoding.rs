@@ -1,10 +1,30 @@
 // Copyright (c) ZeroC, Inc.
 
-use super::{Slice2, Slice2Decoder};
-use crate::decoder::{DecodeError, DecodeResult, Decoder};
+use super::{compute_varint_size_prefix, compute_varuint_size_prefix, Slice2, Slice2Decoder};
+use crate::decoder::{DecodeError, DecodeErrorKind, DecodeResult, Decoder};
 use crate::decoding::implement_slice_decodable_for_primitive_numeric_type;
+use crate::io_types::bit_sequence::BitSequenceReader;
 use crate::try_decode::TryDecode;
 
+#[cfg(feature = "alloc")]
+use crate::decoder::DecodeContext;
+#[cfg(feature = "alloc")]
+use crate::decoding::try_decode_btree_map;
+#[cfg(feature = "std")]
+use crate::decoding::try_decode_hash_map;
+#[cfg(feature = "alloc")]
+use crate::try_decode::{DecodeFn, TryDecodeCollection};
+
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use core::hash::Hash;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
 // TODO ADD COMMENTS TO EVERYTHING IN THIS FILE. RIGHT NOW WE'RE JUST GETTING IT FOR JOE!
 
 // =============================================================================
@@ -12,10 +32,19 @@ use crate::try_decode::TryDecode;
 // =============================================================================
 
 impl TryDecode<Slice2> for i8 {
+    const MIN_ENCODED_SIZE: usize = 1;
+
     fn try_decode(decoder: &mut Slice2Decoder) -> DecodeResult<Self> {
         let byte = decoder.read_byte()?;
         Ok(*byte as i8)
     }
+
+    /// Reads all the bytes at once, then reinterprets them as `i8`s.
+    #[cfg(feature = "alloc")]
+    fn try_decode_elements(decoder: &mut Slice2Decoder, length: usize) -> DecodeResult<Vec<Self>> {
+        let bytes = decoder.read_bytes_exact(length)?;
+        Ok(bytes.iter().map(|byte| *byte as i8).collect())
+    }
 }
 
 implement_slice_decodable_for_primitive_numeric_type!(u16, "TODO", Slice2);
@@ -28,58 +57,232 @@ implement_slice_decodable_for_primitive_numeric_type!(u64, "TODO", Slice2);
 
 impl Slice2Decoder<'_> {
     pub fn try_decode_varint32(&mut self) -> DecodeResult<i32> {
+        let offset = self.position();
         let varint62 = self.try_decode_varint62()?;
 
-        varint62.try_into().map_err(|_| DecodeError::OutOfRange {
-            value: varint62 as i128,
-            min: super::VARINT32_MIN as i128,
-            max: super::VARINT32_MAX as i128,
-            typename: "varint32",
+        varint62.try_into().map_err(|_| {
+            let kind = DecodeErrorKind::OutOfRange {
+                value: varint62 as i128,
+                min: super::VARINT32_MIN as i128,
+                max: super::VARINT32_MAX as i128,
+                typename: "varint32",
+            };
+            DecodeError::new(kind, offset)
         })
     }
 
     pub fn try_decode_varuint32(&mut self) -> DecodeResult<u32> {
+        let offset = self.position();
         let varuint62 = self.try_decode_varuint62()?;
 
-        varuint62.try_into().map_err(|_| DecodeError::OutOfRange {
-            value: varuint62 as i128,
-            min: super::VARUINT32_MIN as i128,
-            max: super::VARUINT32_MAX as i128,
-            typename: "varuint32",
+        varuint62.try_into().map_err(|_| {
+            let kind = DecodeErrorKind::OutOfRange {
+                value: varuint62 as i128,
+                min: super::VARUINT32_MIN as i128,
+                max: super::VARUINT32_MAX as i128,
+                typename: "varuint32",
+            };
+            DecodeError::new(kind, offset)
         })
     }
 
     pub fn try_decode_varint62(&mut self) -> DecodeResult<i64> {
-        let Some(size_prefix_byte) = self.peek_byte() else {
-            return Err(DecodeError::EndOfBuffer)
+        let offset = self.position();
+        let Some(&size_prefix_byte) = self.peek_byte() else {
+            return Err(self.end_of_buffer_error(1, offset))
         };
 
-        let value = match size_prefix_byte & 0b11 {
+        let size_prefix = size_prefix_byte & 0b11;
+        let value = match size_prefix {
             0b00 =>  i8::try_decode(self)? as i64,
             0b01 => i16::try_decode(self)? as i64,
             0b10 => i32::try_decode(self)? as i64,
             0b11 => i64::try_decode(self)?,
             _ => unsafe {
-                std::hint::unreachable_unchecked()
+                core::hint::unreachable_unchecked()
             }
         };
+
+        // Clearing the size prefix leaves us with the pre-shifted value, which is what the encoder computes it from.
+        let preshifted_value = value & !0b11;
+        if self.is_strict() && compute_varint_size_prefix(preshifted_value) != size_prefix as i64 {
+            return Err(non_minimal_varint_error(value >> 2, offset));
+        }
         Ok(value >> 2)
     }
 
     pub fn try_decode_varuint62(&mut self) -> DecodeResult<u64> {
-        let Some(size_prefix_byte) = self.peek_byte() else {
-            return Err(DecodeError::EndOfBuffer)
+        let offset = self.position();
+        let Some(&size_prefix_byte) = self.peek_byte() else {
+            return Err(self.end_of_buffer_error(1, offset))
         };
 
-        let value = match size_prefix_byte & 0b11 {
+        let size_prefix = size_prefix_byte & 0b11;
+        let value = match size_prefix {
             0b00 =>  u8::try_decode(self)? as u64,
             0b01 => u16::try_decode(self)? as u64,
             0b10 => u32::try_decode(self)? as u64,
             0b11 => u64::try_decode(self)?,
             _ => unsafe {
-                std::hint::unreachable_unchecked()
+                core::hint::unreachable_unchecked()
             }
         };
+
+        // Clearing the size prefix leaves us with the pre-shifted value, which is what the encoder computes it from.
+        let preshifted_value = value & !0b11;
+        if self.is_strict() && compute_varuint_size_prefix(preshifted_value) != size_prefix as u64 {
+            return Err(non_minimal_varint_error((value >> 2) as i64, offset));
+        }
         Ok(value >> 2)
     }
 }
+
+/// Returns the error that strict decoders report when a variable-length integer isn't encoded on the minimum number of
+/// bytes. See [`Decoder::with_strict_mode`].
+fn non_minimal_varint_error(value: i64, offset: usize) -> DecodeError {
+    let kind = DecodeErrorKind::IllegalValue {
+        value: value as i128,
+        desc: "variable-length integers must be encoded on the minimum number of bytes",
+    };
+    DecodeError::new(kind, offset)
+}
+
+// =============================================================================
+// Bit sequence implementations
+// =============================================================================
+
+impl<'a> Slice2Decoder<'a> {
+    /// Reads a bit sequence holding `length`-many bits, and returns a reader over them.
+    ///
+    /// Only strict decoders check the unused bits after the last bit, which must be zero. Bits are read from least to
+    /// most significant, so these are the high bits of the last byte. Every bit sequence must be read with this
+    /// function, so that decoding and skipping agree on which bit sequences are valid.
+    pub(crate) fn try_decode_bit_sequence(&mut self, length: usize) -> DecodeResult<BitSequenceReader<'a>> {
+        let offset = self.position();
+        let buffer = self.read_bytes_exact(length.div_ceil(8))?;
+
+        let used_bits_in_last_byte = length % 8;
+        if self.is_strict() && used_bits_in_last_byte != 0 {
+            // SAFETY: the bit sequence can't be empty, since `length` isn't a multiple of 8.
+            let last_byte = buffer[buffer.len() - 1];
+            if last_byte >> used_bits_in_last_byte != 0 {
+                let kind = DecodeErrorKind::IllegalValue {
+                    value: last_byte as i128,
+                    desc: "the unused bits at the end of a bit sequence must be zero",
+                };
+                return Err(DecodeError::new(kind, offset + buffer.len() - 1));
+            }
+        }
+        Ok(BitSequenceReader::new(buffer))
+    }
+}
+
+// =============================================================================
+// Sequence type implementations
+// =============================================================================
+
+#[cfg(feature = "alloc")]
+impl<T> TryDecodeCollection<Slice2, T> for Vec<Option<T>> {
+    fn try_decode_with_fn(decoder: &mut Slice2Decoder, decode_fn: DecodeFn<T, Slice2>) -> DecodeResult<Self> {
+        decoder.nested(|decoder| {
+            // Unset elements aren't encoded at all, so the elements themselves can take up zero bytes.
+            let length = decoder.try_decode_collection_length(0)?;
+
+            // The elements are preceded by a bit sequence, with one bit per element, which says whether it's set or
+            // not. We read it before allocating, since its length is enough to reject most lengths that are too large.
+            let mut bit_sequence_reader = decoder.try_decode_bit_sequence(length)?;
+
+            decoder.increase_heap_allocation_total_for::<Option<T>>(length)?;
+            let mut vector = Vec::with_capacity(length);
+
+            for i in 0..length {
+                let element = match bit_sequence_reader.read_bit() {
+                    true => Some(decode_fn(decoder).at_index(i)?),
+                    false => None,
+                };
+                vector.push(element);
+            }
+            Ok(vector)
+        })
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> TryDecode<Slice2> for Vec<Option<T>>
+    where T: TryDecode<Slice2>,
+{
+    const MIN_ENCODED_SIZE: usize = 1;
+
+    fn try_decode(decoder: &mut Slice2Decoder) -> DecodeResult<Self> {
+        Self::try_decode_with_fn(decoder, T::try_decode)
+    }
+}
+
+// =============================================================================
+// Dictionary type implementations
+// =============================================================================
+
+// A dictionary entry with an optional value is encoded like a compact struct with an optional field. So each entry
+// starts with a bit sequence (holding a single bit, encoded on a single byte), followed by its key, then its value
+// (if it's set).
+#[cfg(feature = "alloc")]
+macro_rules! try_decode_optional_dictionary_body {
+    ($decode_dictionary_fn:ident) => {
+        const MIN_ENCODED_SIZE: usize = 1;
+
+        fn try_decode(decoder: &mut Slice2Decoder) -> DecodeResult<Self> {
+            let decode_fn = |decoder: &mut Slice2Decoder| {
+                let is_set = decoder.try_decode_bit_sequence(1)?.read_bit();
+                let key = K::try_decode(decoder)?;
+                let value = match is_set {
+                    true => Some(V::try_decode(decoder)?),
+                    false => None,
+                };
+                Ok((key, value))
+            };
+            // Each entry has at least its bit sequence and its key, since the value might not be set.
+            let min_entry_size = K::MIN_ENCODED_SIZE.saturating_add(1);
+            $decode_dictionary_fn(decoder, decode_fn, min_entry_size)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V> TryDecode<Slice2> for HashMap<K, Option<V>>
+where
+    K: TryDecode<Slice2> + Eq + Hash,
+    V: TryDecode<Slice2>,
+{
+    try_decode_optional_dictionary_body!(try_decode_hash_map);
+}
+
+#[cfg(feature = "alloc")]
+impl<K, V> TryDecode<Slice2> for BTreeMap<K, Option<V>>
+where
+    K: TryDecode<Slice2> + Ord,
+    V: TryDecode<Slice2>,
+{
+    try_decode_optional_dictionary_body!(try_decode_btree_map);
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::decoder::{DecodeErrorKind, Decoder};
+    use crate::slice2::Slice2;
+    use alloc::collections::BTreeMap;
+
+    #[test]
+    fn only_strict_decoders_check_the_unused_bits_of_dictionary_entries() {
+        // A dictionary with 2 entries: `5 => Some(6)` and `7 => None`, where both entries have unused bits set.
+        let buffer: &[u8] = &[8, 0b0000_0011, 5, 6, 0b1000_0000, 7];
+
+        let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+        let dictionary = decoder.try_decode::<BTreeMap<u8, Option<u8>>>().unwrap();
+        assert_eq!(dictionary, BTreeMap::from([(5, Some(6)), (7, None)]));
+
+        let mut decoder: Decoder<Slice2> = Decoder::new(buffer).with_strict_mode();
+        let error = decoder.try_decode::<BTreeMap<u8, Option<u8>>>().unwrap_err();
+        assert!(matches!(error.kind(), DecodeErrorKind::IllegalValue { value: 3, .. }));
+        assert_eq!(error.offset(), 1);
+    }
+}
@@ -1 +1,452 @@
 // Copyright (c) ZeroC, Inc.
+
+use super::{compute_varint_size_prefix, compute_varuint_size_prefix, Slice2, Slice2Encoder};
+use crate::encoder::{EncodeError, EncodeResult, Encoder};
+use crate::encoding::{implement_slice_encodable_for_borrowed_value_type, implement_slice_encodable_for_primitive_numeric_type};
+use crate::io_types::bit_sequence::BitSequenceWriter;
+use crate::try_encode::{EncodeFn, TryEncode, TryEncodeCollection};
+use crate::Encoding;
+
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+// =============================================================================
+// Fixed-length type implementations
+// =============================================================================
+
+impl TryEncode<Slice2> for i8 {
+    /// Encodes this i8 as a single byte, in two's component form.
+    fn try_encode(self, encoder: &mut Slice2Encoder) -> EncodeResult<()> {
+        // In memory, signed integers are guaranteed to use a two's complement representation.
+        // Casting between i8 and u8 is no-op, and doesn't change this representation.
+        encoder.write_byte(self as u8)
+    }
+}
+implement_slice_encodable_for_borrowed_value_type!(i8, Slice2);
+
+implement_slice_encodable_for_primitive_numeric_type!(
+    u16,
+    "Encodes this u16 on 2 bytes (little endian), as-is.",
+    Slice2
+);
+implement_slice_encodable_for_primitive_numeric_type!(
+    u32,
+    "Encodes this u32 on 4 bytes (little endian), as-is.",
+    Slice2
+);
+implement_slice_encodable_for_primitive_numeric_type!(
+    u64,
+    "Encodes this u64 on 8 bytes (little endian), as-is.",
+    Slice2
+);
+
+// =============================================================================
+// Variable-length integer type implementations
+// =============================================================================
+
+macro_rules! encode_variable_integer {
+    ($value:ident, $encoder:ident, $compute_size_prefix:ident) => {{
+        // Shift the value over by 2 bits to reserve room for the size prefix.
+        $value <<= 2;
+
+        // Calculate the minimum necessary size prefix, and OR it into the first 2 bits of value (which we reserved).
+        let size_prefix = $compute_size_prefix($value);
+        $value |= size_prefix;
+
+        // Only encode the first `n` bytes of the value, where `n` is 2^size_prefix, so either 1, 2, 4, or 8 bytes.
+        let encoded_length = usize::pow(2, size_prefix as u32);
+        $encoder.write_bytes(&$value.to_le_bytes()[..encoded_length])
+    }}
+}
+
+impl Slice2Encoder<'_> {
+    /// Encodes the provided value as a `varint32`, using the minimum number of bytes necessary.
+    ///
+    /// This is identical to encoding it as a `varint62`; every `i32` is in range, so this never returns
+    /// [`OutOfRange`](EncodeError::OutOfRange).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let mut buffer = [0; 16];
+    /// let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+    /// encoder.try_encode_varint32(-1).unwrap();
+    /// encoder.try_encode_varint32(100).unwrap();
+    ///
+    /// assert_eq!(encoder.as_bytes(), &[0b1111_1100, 0b1001_0001, 0b0000_0001]);
+    /// ```
+    pub fn try_encode_varint32(&mut self, value: i32) -> EncodeResult<()> {
+        let mut value = value as i64;
+        encode_variable_integer!(value, self, compute_varint_size_prefix)
+    }
+
+    /// Encodes the provided value as a `varuint32`, using the minimum number of bytes necessary.
+    ///
+    /// This is identical to encoding it as a `varuint62`; every `u32` is in range, so this never returns
+    /// [`OutOfRange`](EncodeError::OutOfRange).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// let mut buffer = [0; 16];
+    /// let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+    /// encoder.try_encode_varuint32(63).unwrap();
+    /// encoder.try_encode_varuint32(64).unwrap();
+    ///
+    /// assert_eq!(encoder.as_bytes(), &[0b1111_1100, 0b0000_0001, 0b0000_0001]);
+    /// ```
+    pub fn try_encode_varuint32(&mut self, value: u32) -> EncodeResult<()> {
+        let mut value = value as u64;
+        encode_variable_integer!(value, self, compute_varuint_size_prefix)
+    }
+
+    /// Encodes the provided value as a `varint62`, using the minimum number of bytes necessary.
+    ///
+    /// If the value is less than [`VARINT62_MIN`](super::VARINT62_MIN) or greater than
+    /// [`VARINT62_MAX`](super::VARINT62_MAX), this returns [`OutOfRange`](EncodeError::OutOfRange) instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::{Slice2, VARINT62_MAX};
+    /// let mut buffer = [0; 16];
+    /// let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+    /// encoder.try_encode_varint62(-32).unwrap();
+    /// encoder.try_encode_varint62(VARINT62_MAX).unwrap();
+    /// assert_eq!(encoder.as_bytes(), &[0x80, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]);
+    ///
+    /// assert!(encoder.try_encode_varint62(VARINT62_MAX + 1).is_err());
+    /// ```
+    pub fn try_encode_varint62(&mut self, value: i64) -> EncodeResult<()> {
+        if !(super::VARINT62_MIN..=super::VARINT62_MAX).contains(&value) {
+            return Err(EncodeError::OutOfRange {
+                value: value as i128,
+                min: super::VARINT62_MIN as i128,
+                max: super::VARINT62_MAX as i128,
+                typename: "varint62",
+            });
+        }
+
+        let mut value = value;
+        encode_variable_integer!(value, self, compute_varint_size_prefix)
+    }
+
+    /// Encodes the provided value as a `varuint62`, using the minimum number of bytes necessary.
+    ///
+    /// If the value is greater than [`VARUINT62_MAX`](super::VARUINT62_MAX), this returns
+    /// [`OutOfRange`](EncodeError::OutOfRange) instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::{Slice2, VARUINT62_MAX};
+    /// let mut buffer = [0; 16];
+    /// let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+    /// encoder.try_encode_varuint62(16_384).unwrap();
+    /// assert_eq!(encoder.as_bytes(), &[0b0000_0010, 0, 1, 0]);
+    ///
+    /// assert!(encoder.try_encode_varuint62(VARUINT62_MAX + 1).is_err());
+    /// ```
+    pub fn try_encode_varuint62(&mut self, value: u64) -> EncodeResult<()> {
+        // We don't check the `MIN` because it's 0, and it's impossible for a `u64` to hold a negative integer.
+        if value > super::VARUINT62_MAX {
+            return Err(EncodeError::OutOfRange {
+                value: value as i128,
+                min: super::VARUINT62_MIN as i128,
+                max: super::VARUINT62_MAX as i128,
+                typename: "varuint62",
+            });
+        }
+
+        let mut value = value;
+        encode_variable_integer!(value, self, compute_varuint_size_prefix)
+    }
+}
+
+// =============================================================================
+// Sequence type implementations
+// =============================================================================
+
+impl<'a, T> TryEncodeCollection<Slice2, &'a T> for &'a [Option<T>] {
+    /// Encodes this slice of optionals as a Slice sequence by writing its length (encoded as a size), followed by a
+    /// bit sequence, followed by its elements, encoded in order.
+    ///
+    /// If an element is `Some`, a `1` is written to the bit sequence, and the element's value is encoded using the
+    /// provided `encode_fn` function. Otherwise a `0` is written to the bit sequence and the element is skipped.
+    fn try_encode_with_fn(self, encoder: &mut Slice2Encoder, encode_fn: EncodeFn<&'a T, Slice2>) -> EncodeResult<()> {
+        let length = self.len();
+        Slice2::try_encode_size(length, encoder)?;
+
+        // Reserve space in the encoder's buffer for the bit-sequence, and fill it in before encoding any elements.
+        let bit_sequence_buffer = encoder.reserve(length.div_ceil(8))?;
+        let mut bit_sequence_writer = BitSequenceWriter::new(bit_sequence_buffer);
+        for element in self {
+            bit_sequence_writer.write_bit(element.is_some());
+        }
+
+        for value in self.iter().flatten() {
+            encode_fn(value, encoder)?;
+        }
+        Ok(())
+    }
+}
+
+/// Automatically implement `TryEncode` for slices of optionals whose elements are also `TryEncode`.
+impl<'a, T> TryEncode<Slice2> for &'a [Option<T>]
+    where &'a T: TryEncode<Slice2>,
+{
+    /// This delegates to [`try_encode_with_fn`](TryEncodeCollection::try_encode_with_fn), using the element type's
+    /// `try_encode` function.
+    fn try_encode(self, encoder: &mut Slice2Encoder) -> EncodeResult<()> {
+        self.try_encode_with_fn(encoder, <&T>::try_encode)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T> TryEncodeCollection<Slice2, &'a T> for &'a Vec<Option<T>> {
+    /// Delegates to the implementation for `&[Option<T>]`.
+    #[inline(always)]
+    fn try_encode_with_fn(self, encoder: &mut Slice2Encoder, encode_fn: EncodeFn<&'a T, Slice2>) -> EncodeResult<()> {
+        self.as_slice().try_encode_with_fn(encoder, encode_fn)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T> TryEncode<Slice2> for &'a Vec<Option<T>>
+    where &'a T: TryEncode<Slice2>,
+{
+    /// Delegates to the implementation for `&[Option<T>]`.
+    #[inline(always)]
+    fn try_encode(self, encoder: &mut Slice2Encoder) -> EncodeResult<()> {
+        self.as_slice().try_encode(encoder)
+    }
+}
+
+// =============================================================================
+// Dictionary type implementations
+// =============================================================================
+
+/// This macro implements `TryEncode` for dictionary types with optional values. These can only be encoded with Slice2.
+///
+/// An entry with an optional value is encoded like a compact struct with an optional field. So each entry starts with
+/// a bit sequence (holding a single bit, encoded on a single byte), followed by its key, then its value (if it's set).
+macro_rules! implement_slice_encodable_for_optional_dictionary_type {
+    ($ty:ident, $feature:literal) => {
+
+#[cfg(feature = $feature)]
+#[doc = concat!("Automatically implement `TryEncode` for ", stringify!($ty), "s with optional values whose keys and values are both `TryEncode`.")]
+impl<'a, K, V> TryEncode<Slice2> for &'a $ty<K, Option<V>>
+where
+    &'a K: TryEncode<Slice2>,
+    &'a V: TryEncode<Slice2>,
+{
+    /// This delegates to [`try_encode_with_fn`](TryEncodeCollection::try_encode_with_fn), using the `try_encode`
+    /// functions of the key and value types.
+    fn try_encode(self, encoder: &mut Slice2Encoder) -> EncodeResult<()> {
+        self.try_encode_with_fn(encoder, |(key, value), encoder| {
+            // Writing a bool is equivalent to a single element bit sequence; `1` is the value is `Some`, `0` otherwise.
+            value.is_some().try_encode(encoder)?;
+            key.try_encode(encoder)?;
+            if let Some(v) = value {
+                v.try_encode(encoder)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+    }
+}
+
+implement_slice_encodable_for_optional_dictionary_type!(HashMap, "std");
+implement_slice_encodable_for_optional_dictionary_type!(BTreeMap, "alloc");
+
+// =============================================================================
+// Tagged field implementations
+// =============================================================================
+
+/// The number of bytes that are reserved for the size of each tagged field, before its value is encoded.
+const TAGGED_FIELD_SIZE_LENGTH: usize = 4;
+
+impl Slice2Encoder<'_> {
+    /// Encodes a tagged field with the provided tag, by writing its tag (encoded as a `varint32`), followed by the size
+    /// of its value (encoded as a `varuint62` on 4 bytes), followed by the value itself. If the value is `None`, nothing
+    /// is written.
+    ///
+    /// Tagged fields must be encoded in strictly increasing order of their tags, and each struct's tagged fields must
+    /// be followed by a call to [`try_encode_tag_end_marker`](Self::try_encode_tag_end_marker). If `tag` isn't greater
+    /// than the tag of the previous tagged field, this returns [`TagOrderViolation`](EncodeError::TagOrderViolation)
+    /// instead, even if the value is `None`. Tags can't be negative, since `-1` is used for the end marker.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::{EncodeError, Encoder};
+    /// # use slice_encoding::slice2::Slice2;
+    /// let mut buffer = [0; 16];
+    /// let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+    /// encoder.try_encode_tagged(3, Some(7_u8)).unwrap();
+    /// encoder.try_encode_tagged(5, None::<u8>).unwrap();
+    ///
+    /// let error = encoder.try_encode_tagged(4, Some(9_u8)).unwrap_err();
+    /// assert_eq!(error, EncodeError::TagOrderViolation { previous: 5, current: 4 });
+    ///
+    /// encoder.try_encode_tag_end_marker().unwrap();
+    /// assert_eq!(encoder.as_bytes(), &[0b0000_1100, 0b0000_0110, 0, 0, 0, 7, 0b1111_1100]);
+    /// ```
+    pub fn try_encode_tagged<T: TryEncode<Slice2>>(&mut self, tag: i32, value: Option<T>) -> EncodeResult<()> {
+        if tag < 0 {
+            return Err(EncodeError::OutOfRange {
+                value: tag as i128,
+                min: 0,
+                max: super::VARINT32_MAX as i128,
+                typename: "tag",
+            });
+        }
+        self.start_tagged_field(tag)?;
+
+        if let Some(value) = value {
+            self.try_encode_varint32(tag)?;
+
+            // We don't know the value's size until it's been encoded, so we reserve space for it, and fill it in after.
+            self.reserve(TAGGED_FIELD_SIZE_LENGTH)?;
+            let start = self.position();
+            value.try_encode(self)?;
+            let size = self.position() - start;
+
+            // The size is encoded as a 4 byte `varuint62`, so it must fit in the 30 bits that aren't the size prefix.
+            let max_size = u32::MAX >> 2;
+            if size > max_size as usize {
+                return Err(EncodeError::OutOfRange {
+                    value: size as i128,
+                    min: 0,
+                    max: max_size as i128,
+                    typename: "tagged field size",
+                });
+            }
+            let encoded_size = ((size as u32) << 2) | 0b10;
+            self.as_bytes_mut()[start - TAGGED_FIELD_SIZE_LENGTH..start].copy_from_slice(&encoded_size.to_le_bytes());
+        }
+
+        self.end_tagged_field(tag);
+        Ok(())
+    }
+
+    /// Encodes the end marker that follows a struct's tagged fields (`-1` encoded as a `varint32`).
+    ///
+    /// After this, the next tagged field can have any tag, since it belongs to a different struct.
+    pub fn try_encode_tag_end_marker(&mut self) -> EncodeResult<()> {
+        self.try_encode_varint32(-1)?;
+        self.end_tagged_fields();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoder::Decoder;
+    use crate::encoder::Encoder;
+    use crate::slice2::*;
+
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    #[test]
+    fn varints_round_trip_at_every_size_boundary() {
+        let values = [
+            VARINT62_MIN, i32::MIN as i64 - 1, i32::MIN as i64, i16::MIN as i64 - 1, i16::MIN as i64, -33, -32, -1,
+            0, 31, 32, i16::MAX as i64, i16::MAX as i64 + 1, i32::MAX as i64, i32::MAX as i64 + 1, VARINT62_MAX,
+        ];
+
+        let mut buffer = [0; 8];
+        for value in values {
+            let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+            encoder.try_encode_varint62(value).unwrap();
+            let length = encoder.position();
+
+            let mut decoder: Decoder<Slice2> = Decoder::new(&buffer[..length]).with_strict_mode();
+            assert_eq!(decoder.try_decode_varint62(), Ok(value));
+            assert_eq!(decoder.remaining(), 0);
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn sequences_of_optionals_round_trip() {
+        let values = [Some(1_i32), None, None, Some(-7), None, None, None, None, Some(9)];
+
+        let mut buffer = [0; 32];
+        let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+        encoder.try_encode(values.as_slice()).unwrap();
+        let length = encoder.position();
+
+        // 1 byte for the size, 2 bytes for the bit sequence, and 4 bytes for each of the set elements.
+        assert_eq!(length, 1 + 2 + (3 * 4));
+
+        let mut decoder: Decoder<Slice2> = Decoder::new(&buffer[..length]).with_strict_mode();
+        assert_eq!(decoder.try_decode::<Vec<Option<i32>>>().unwrap(), values);
+        assert_eq!(decoder.remaining(), 0);
+    }
+
+    #[test]
+    fn tagged_fields_of_nested_structs_are_ordered_separately() {
+        use crate::encoder::{EncodeError, EncodeResult};
+        use crate::try_encode::TryEncode;
+
+        // A struct whose only field is tagged.
+        struct Inner(u8);
+
+        impl TryEncode<Slice2> for Inner {
+            fn try_encode(self, encoder: &mut Encoder<Slice2>) -> EncodeResult<()> {
+                encoder.try_encode_tagged(0, Some(self.0))?;
+                encoder.try_encode_tag_end_marker()
+            }
+        }
+
+        let mut buffer = [0; 32];
+        let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+        encoder.try_encode_tagged(1, Some(Inner(2))).unwrap();
+        encoder.try_encode_tagged(2, Some(Inner(3))).unwrap();
+
+        let error = encoder.try_encode_tagged(2, Some(Inner(4))).unwrap_err();
+        assert_eq!(error, EncodeError::TagOrderViolation { previous: 2, current: 2 });
+        encoder.try_encode_tag_end_marker().unwrap();
+
+        // Each tagged field is its tag, a 4 byte size, and the inner struct (which is a tagged field and end marker).
+        let inner_size = 1 + 4 + 1 + 1;
+        assert_eq!(encoder.position(), 2 * (1 + 4 + inner_size) + 1);
+        assert_eq!(&encoder.as_bytes()[..5], &[0b0000_0100, (inner_size << 2) as u8 | 0b10, 0, 0, 0]);
+
+        let error = encoder.try_encode_tagged(-1, Some(5_u8)).unwrap_err();
+        assert!(matches!(error, EncodeError::OutOfRange { value: -1, .. }));
+    }
+
+    #[test]
+    fn varuints_round_trip_at_every_size_boundary() {
+        let values = [
+            0, 63, 64, 16_383, 16_384, 1_073_741_823, 1_073_741_824, VARUINT62_MAX,
+        ];
+
+        let mut buffer = [0; 8];
+        for value in values {
+            let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+            encoder.try_encode_varuint62(value).unwrap();
+            let length = encoder.position();
+
+            let mut decoder: Decoder<Slice2> = Decoder::new(&buffer[..length]).with_strict_mode();
+            assert_eq!(decoder.try_decode_varuint62(), Ok(value));
+            assert_eq!(decoder.remaining(), 0);
+        }
+    }
+}
@@ -3,9 +3,14 @@
 // These modules are private because they don't export any types, just implementations.
 mod decoding;
 mod encoding;
+mod skipping;
 
-use crate::decoder::{DecodeError, DecodeResult, Decoder};
-use crate::encoder::{Encoder, EncodeResult};
+// Reading and writing frames asynchronously requires a dependency on `tokio`.
+#[cfg(feature = "tokio")]
+mod async_io;
+
+use crate::decoder::{DecodeError, DecodeErrorKind, DecodeResult, Decoder};
+use crate::encoder::{EncodeError, Encoder, EncodeResult};
 use crate::Encoding;
 
 // TODO
@@ -25,9 +30,46 @@ pub const VARUINT62_MIN: u64 = u64::MIN >> 2;
 // TODO
 pub const VARUINT62_MAX: u64 = u64::MAX >> 2;
 
+/// Returns the size prefix of the smallest encoding that can hold the provided (already shifted) `varint62` value.
+/// This is shared by encoding, which uses it to pick the encoding, and strict decoding, which uses it to validate one.
+fn compute_varint_size_prefix(preshifted_value: i64) -> i64 {
+    // Integers must be the same type to be comparable, so we store pre-cast constants to de-clutter the comparisons.
+    const I8_MAX: i64 = i8::MAX as i64;
+    const I8_MIN: i64 = i8::MIN as i64;
+    const I16_MAX: i64 = i16::MAX as i64;
+    const I16_MIN: i64 = i16::MIN as i64;
+    const I32_MAX: i64 = i32::MAX as i64;
+    const I32_MIN: i64 = i32::MIN as i64;
+
+    match preshifted_value {
+        I8_MIN  ..= I8_MAX  => 0b00,  // 0
+        I16_MIN ..= I16_MAX => 0b01,  // 1
+        I32_MIN ..= I32_MAX => 0b10,  // 2
+        _                   => 0b11,  // 3
+    }
+}
+
+/// Returns the size prefix of the smallest encoding that can hold the provided (already shifted) `varuint62` value.
+fn compute_varuint_size_prefix(preshifted_value: u64) -> u64 {
+    // Integers must be the same type to be comparable, so we store pre-cast constants to de-clutter the comparisons.
+    const U8_MAX: u64 = u8::MAX as u64;
+    const U16_MAX: u64 = u16::MAX as u64;
+    const U32_MAX: u64 = u32::MAX as u64;
+
+    match preshifted_value {
+        x if x <= U8_MAX  => 0b00,  // 0
+        x if x <= U16_MAX => 0b01,  // 1
+        x if x <= U32_MAX => 0b10,  // 2
+        _                 => 0b11,  // 3
+    }
+}
+
 /// TODO
 type Slice2Decoder<'a> = Decoder<'a, Slice2>;
 
+/// TODO
+type Slice2Encoder<'a> = Encoder<'a, Slice2>;
+
 /// TODO
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Slice2;
@@ -35,17 +77,39 @@ pub struct Slice2;
 impl Encoding for Slice2 {
     /// TODO
     fn try_decode_size(decoder: &mut Slice2Decoder) -> DecodeResult<usize> {
+        let offset = decoder.position();
         decoder.try_decode_varuint62().and_then(|uint64| {
             uint64.try_into().map_err(|_| {
-                DecodeError::InvalidData {
-                    desc: "TODO",
-                }
+                let kind = DecodeErrorKind::InvalidData {
+                    desc: "size is too large to fit into a usize on this platform",
+                };
+                DecodeError::new(kind, offset)
             })
         })
     }
 
-    /// TODO
-    fn try_encode_size(size: usize, encoder: &mut Encoder<Slice2>) -> EncodeResult<()> {
-        todo!() // TODO
+    /// Encodes a size with the Slice2 encoding, as a `varuint62`.
+    ///
+    /// If the size is larger than [`VARUINT62_MAX`], this returns
+    /// [`CollectionTooLarge`](EncodeError::CollectionTooLarge) instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::encoder::Encoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// # use slice_encoding::Encoding;
+    /// let mut buffer = [0; 16];
+    /// let mut encoder: Encoder<Slice2> = Encoder::from_slice(&mut buffer);
+    /// Slice2::try_encode_size(3, &mut encoder).unwrap();
+    /// Slice2::try_encode_size(300, &mut encoder).unwrap();
+    ///
+    /// assert_eq!(encoder.as_bytes(), &[0b0000_1100, 0b1011_0001, 0b0000_0100]);
+    /// ```
+    fn try_encode_size(size: usize, encoder: &mut Slice2Encoder) -> EncodeResult<()> {
+        match u64::try_from(size) {
+            Ok(size) if size <= VARUINT62_MAX => encoder.try_encode_varuint62(size),
+            _ => Err(EncodeError::CollectionTooLarge { length: size, max: VARUINT62_MAX }),
+        }
     }
 }
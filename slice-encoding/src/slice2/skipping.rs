@@ -0,0 +1,204 @@
+// Copyright (c) ZeroC, Inc.
+
+use super::{Slice2, Slice2Decoder};
+use crate::decoder::{DecodeContext, DecodeResult, Decoder};
+use crate::skipping::implement_slice_skippable_for_fixed_size_type;
+use crate::try_skip::TrySkip;
+
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+// =============================================================================
+// Fixed-length type implementations
+// =============================================================================
+
+implement_slice_skippable_for_fixed_size_type!(i8, 1, Slice2);
+implement_slice_skippable_for_fixed_size_type!(u16, 2, Slice2);
+implement_slice_skippable_for_fixed_size_type!(u32, 4, Slice2);
+implement_slice_skippable_for_fixed_size_type!(u64, 8, Slice2);
+
+// =============================================================================
+// Variable-length integer type implementations
+// =============================================================================
+
+impl Slice2Decoder<'_> {
+    /// Advances this decoder past a variable-length integer, without decoding it.
+    ///
+    /// All the variable-length integer types share the same size prefix, so this can skip any of them:
+    /// `varint32`, `varuint32`, `varint62`, and `varuint62`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use slice_encoding::decoder::Decoder;
+    /// # use slice_encoding::slice2::Slice2;
+    /// // A varint62 encoded on 4 bytes, followed by a `u8`.
+    /// let buffer: &[u8] = &[0b0000_0010, 0, 0, 0, 9];
+    /// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+    ///
+    /// decoder.try_skip_varint().unwrap();
+    /// assert_eq!(decoder.try_decode::<u8>(), Ok(9));
+    /// ```
+    pub fn try_skip_varint(&mut self) -> DecodeResult<()> {
+        let Some(size_prefix_byte) = self.peek_byte() else {
+            return Err(self.end_of_buffer_error(1, self.position()))
+        };
+
+        // The size prefix is the number of bytes the integer is encoded on, as a power of 2.
+        let size = 1 << (size_prefix_byte & 0b11);
+        self.read_bytes_exact(size).map(|_| ())
+    }
+}
+
+// =============================================================================
+// Sequence type implementations
+// =============================================================================
+
+impl<T> TrySkip<Slice2> for [Option<T>]
+    where T: TrySkip<Slice2>,
+{
+    /// Skips a sequence of optional elements by decoding its length and bit sequence, then skipping each of the
+    /// elements that are set.
+    fn try_skip(decoder: &mut Slice2Decoder) -> DecodeResult<()> {
+        decoder.nested(|decoder| {
+            // Unset elements aren't encoded at all, so the elements themselves can take up zero bytes.
+            let length = decoder.try_decode_collection_length(0)?;
+
+            let mut bit_sequence_reader = decoder.try_decode_bit_sequence(length)?;
+
+            for i in 0..length {
+                if bit_sequence_reader.read_bit() {
+                    T::try_skip(decoder).at_index(i)?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> TrySkip<Slice2> for Vec<Option<T>>
+    where T: TrySkip<Slice2>,
+{
+    /// Delegates to the implementation for `[Option<T>]`.
+    #[inline(always)]
+    fn try_skip(decoder: &mut Slice2Decoder) -> DecodeResult<()> {
+        <[Option<T>]>::try_skip(decoder)
+    }
+}
+
+// =============================================================================
+// Dictionary type implementations
+// =============================================================================
+
+/// Skips a dictionary with optional values by decoding its length, then skipping each of its entries. Each entry
+/// starts with a bit sequence saying whether its value is set, followed by its key, and then its value (if it's set).
+#[cfg(feature = "alloc")]
+fn try_skip_optional_dictionary<K, V>(decoder: &mut Slice2Decoder) -> DecodeResult<()>
+where
+    K: TrySkip<Slice2>,
+    V: TrySkip<Slice2>,
+{
+    decoder.nested(|decoder| {
+        // Each entry has at least its bit sequence and its key, since the value might not be set.
+        let min_entry_size = K::FIXED_SIZE.unwrap_or(0).saturating_add(1);
+        let length = decoder.try_decode_collection_length(min_entry_size)?;
+
+        for i in 0..length {
+            let is_set = decoder.try_decode_bit_sequence(1).at_index(i)?.read_bit();
+            K::try_skip(decoder).at_index(i)?;
+            if is_set {
+                V::try_skip(decoder).at_index(i)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+#[cfg(feature = "std")]
+impl<K, V> TrySkip<Slice2> for HashMap<K, Option<V>>
+where
+    K: TrySkip<Slice2>,
+    V: TrySkip<Slice2>,
+{
+    fn try_skip(decoder: &mut Slice2Decoder) -> DecodeResult<()> {
+        try_skip_optional_dictionary::<K, V>(decoder)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K, V> TrySkip<Slice2> for BTreeMap<K, Option<V>>
+where
+    K: TrySkip<Slice2>,
+    V: TrySkip<Slice2>,
+{
+    fn try_skip(decoder: &mut Slice2Decoder) -> DecodeResult<()> {
+        try_skip_optional_dictionary::<K, V>(decoder)
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use crate::decoder::Decoder;
+    use crate::slice2::Slice2;
+    use crate::try_decode::TryDecode;
+    use crate::try_skip::TrySkip;
+    use alloc::collections::BTreeMap;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn skipping_and_decoding_agree_on_bit_sequences_with_unused_bits_set() {
+        // A dictionary with 1 entry, `5 => None`, whose entry has an unused bit set. Followed by a trailing `u8`.
+        let dictionary: &[u8] = &[4, 0b0000_0010, 5, 9];
+        // A sequence of 3 unset optional `u8`s, whose bit sequence has an unused bit set. Followed by a trailing `u8`.
+        let sequence: &[u8] = &[12, 0b0000_1000, 9];
+
+        fn check<T: TryDecode<Slice2> + TrySkip<Slice2>>(buffer: &[u8], strict: bool) {
+            let new_decoder = || match strict {
+                true => Decoder::<Slice2>::new(buffer).with_strict_mode(),
+                false => Decoder::<Slice2>::new(buffer),
+            };
+
+            let mut decoder = new_decoder();
+            let decoded = decoder.try_decode::<T>().map(|_| decoder.position());
+            let mut decoder = new_decoder();
+            let skipped = decoder.skip::<T>().map(|_| decoder.position());
+
+            assert_eq!(decoded, skipped);
+            assert_eq!(decoded.is_ok(), !strict);
+        }
+
+        for strict in [false, true] {
+            check::<BTreeMap<u8, Option<u8>>>(dictionary, strict);
+            check::<Vec<Option<u8>>>(sequence, strict);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn skipping_huge_fixed_size_collections_from_a_reader_does_not_overflow() {
+        use crate::decoder::DecodeErrorKind;
+        use crate::io_types::reader::ReadBuffer;
+
+        // A length of `VARUINT62_MAX`, which is too large for its elements to fit in memory.
+        // Readers don't know how many bytes are remaining, so they can't reject this length up front.
+        let payload: &[u8] = &[0xFF; 8];
+
+        let buffer = ReadBuffer::new();
+        let mut decoder: Decoder<Slice2> = Decoder::from_reader(payload, &buffer);
+        let error = decoder.skip::<[u64]>().unwrap_err();
+        assert!(matches!(error.kind(), DecodeErrorKind::InvalidData { .. }));
+        assert_eq!(error.offset(), 0);
+
+        let buffer = ReadBuffer::new();
+        let mut decoder: Decoder<Slice2> = Decoder::from_reader(payload, &buffer);
+        let error = decoder.skip::<BTreeMap<u32, u64>>().unwrap_err();
+        assert!(matches!(error.kind(), DecodeErrorKind::InvalidData { .. }));
+        assert_eq!(error.offset(), 0);
+    }
+}
@@ -0,0 +1,250 @@
+// Copyright (c) ZeroC, Inc.
+
+//! Borrowed, typed views over sequences of fixed-size numeric types.
+//!
+//! Decoding a sequence into a `Vec` requires allocating and converting every element up-front. A [`SliceView`]
+//! instead borrows the sequence's encoded bytes straight from the decoder's input, and only converts elements from
+//! their little endian representation when they're accessed. This makes it possible to scan very large sequences
+//! without allocating any memory.
+
+use crate::decoder::{DecodeResult, Decoder};
+use crate::try_decode::{TryDecode, TryDecodeBorrowed};
+use crate::Encoding;
+
+use core::fmt::Debug;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::slice::ChunksExact;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Numeric types that can be viewed in place by a [`SliceView`].
+///
+/// This trait is sealed, and cannot be implemented outside of this crate.
+pub trait ViewElement: private::Sealed + Copy + Debug + 'static {
+    /// The number of bytes each value of this type is encoded on.
+    const SIZE: usize;
+
+    /// Converts exactly [`SIZE`](Self::SIZE) little endian bytes into a value of this type.
+    fn from_le_slice(bytes: &[u8]) -> Self;
+}
+
+macro_rules! implement_view_element_for_primitive_numeric_type {
+    ($($ty:ty),+) => {$(
+        impl private::Sealed for $ty {}
+
+        impl ViewElement for $ty {
+            const SIZE: usize = core::mem::size_of::<$ty>();
+
+            #[inline(always)]
+            fn from_le_slice(bytes: &[u8]) -> Self {
+                let mut array = [0; core::mem::size_of::<$ty>()];
+                array.copy_from_slice(bytes);
+                <$ty>::from_le_bytes(array)
+            }
+        }
+    )+}
+}
+
+implement_view_element_for_primitive_numeric_type!(u16, i32, u32, i64, u64, f32, f64);
+
+/// A sequence of `T`s which borrows its encoded bytes directly from a decoder's input.
+///
+/// Elements are only decoded when they're accessed, with [`get`](Self::get) or [`iter`](Self::iter).
+///
+/// # Examples
+///
+/// ```
+/// # use slice_encoding::decoder::Decoder;
+/// # use slice_encoding::slice2::Slice2;
+/// # use slice_encoding::slice_view::SliceView;
+/// // A sequence of 3 `i32`s: `[1, -1, 300]`.
+/// let buffer: &[u8] = &[12, 1, 0, 0, 0, 255, 255, 255, 255, 44, 1, 0, 0];
+/// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+///
+/// let view: SliceView<i32> = decoder.try_decode_borrowed().unwrap();
+/// assert_eq!(view.len(), 3);
+/// assert_eq!(view.get(1), Some(-1));
+/// assert_eq!(view.get(3), None);
+/// assert_eq!(view.iter().sum::<i32>(), 300);
+/// ```
+#[derive(Clone, Copy)]
+pub struct SliceView<'a, T: ViewElement> {
+    bytes: &'a [u8],
+    _marker: PhantomData<T>,
+}
+
+impl<'a, T: ViewElement> SliceView<'a, T> {
+    /// Creates a view over the provided bytes, which must hold a whole number of `T`s, encoded in little endian.
+    ///
+    /// If the length of `bytes` isn't a multiple of [`T::SIZE`](ViewElement::SIZE), this returns `None` instead.
+    pub fn new(bytes: &'a [u8]) -> Option<Self> {
+        match bytes.len() % T::SIZE {
+            0 => Some(SliceView { bytes, _marker: PhantomData }),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of elements in this view.
+    pub fn len(&self) -> usize {
+        self.bytes.len() / T::SIZE
+    }
+
+    /// Returns `true` if this view contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Decodes and returns the element at `index`, or `None` if `index` is out of bounds.
+    pub fn get(&self, index: usize) -> Option<T> {
+        self.iter().nth(index)
+    }
+
+    /// Returns an iterator which lazily decodes this view's elements, in order.
+    pub fn iter(&self) -> SliceViewIter<'a, T> {
+        SliceViewIter { chunks: self.bytes.chunks_exact(T::SIZE), _marker: PhantomData }
+    }
+
+    /// Returns the encoded bytes this view borrows from.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+impl<T: ViewElement> Debug for SliceView<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T: ViewElement> IntoIterator for SliceView<'a, T> {
+    type Item = T;
+    type IntoIter = SliceViewIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T: ViewElement> IntoIterator for &SliceView<'a, T> {
+    type Item = T;
+    type IntoIter = SliceViewIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, E: Encoding, T> TryDecodeBorrowed<'a, E> for SliceView<'a, T>
+    where T: ViewElement + TryDecode<E>,
+{
+    /// Decodes a sequence of `T`s by borrowing its elements' bytes from the decoder's input, without converting them.
+    fn try_decode_borrowed(decoder: &mut Decoder<'a, E>) -> DecodeResult<Self> {
+        let offset = decoder.position();
+        let length = decoder.try_decode_collection_length(T::SIZE)?;
+
+        let byte_size = Decoder::<E>::collection_byte_size(length, T::SIZE, offset)?;
+        let bytes = decoder.read_bytes_exact(byte_size)?;
+        Ok(SliceView { bytes, _marker: PhantomData })
+    }
+}
+
+/// An iterator which lazily decodes the elements of a [`SliceView`]. See [`SliceView::iter`].
+#[derive(Clone, Debug)]
+pub struct SliceViewIter<'a, T: ViewElement> {
+    chunks: ChunksExact<'a, u8>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: ViewElement> Iterator for SliceViewIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.chunks.next().map(T::from_le_slice)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.chunks.nth(n).map(T::from_le_slice)
+    }
+}
+
+impl<T: ViewElement> DoubleEndedIterator for SliceViewIter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.chunks.next_back().map(T::from_le_slice)
+    }
+}
+
+impl<T: ViewElement> ExactSizeIterator for SliceViewIter<'_, T> {}
+
+impl<T: ViewElement> FusedIterator for SliceViewIter<'_, T> {}
+
+#[cfg(all(test, feature = "slice2"))]
+mod tests {
+    use super::*;
+    use crate::decoder::DecodeErrorKind;
+    use crate::slice2::Slice2;
+
+    #[test]
+    fn views_decode_their_elements_lazily() {
+        // A sequence of 3 `u16`s: `[1, 256, 65535]`.
+        let buffer: &[u8] = &[12, 1, 0, 0, 1, 255, 255];
+        let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+        let view: SliceView<u16> = decoder.try_decode_borrowed().unwrap();
+
+        assert_eq!((view.len(), view.is_empty()), (3, false));
+        assert_eq!((view.get(0), view.get(2), view.get(3)), (Some(1), Some(65535), None));
+        assert!(view.iter().eq([1, 256, 65535]));
+        assert!(view.iter().rev().eq([65535, 256, 1]));
+        assert_eq!(view.iter().len(), 3);
+        assert_eq!(view.as_bytes(), &buffer[1..]);
+        assert_eq!(decoder.remaining(), 0);
+    }
+
+    #[test]
+    fn views_can_borrow_unaligned_bytes() {
+        // A `u8`, followed by a sequence of 2 `u64`s: `[1, u64::MAX]`. So the elements start at an odd offset.
+        let mut buffer = [0; 18];
+        buffer[..2].copy_from_slice(&[7, 8]);
+        buffer[2] = 1;
+        buffer[10..].fill(255);
+
+        let mut decoder: Decoder<Slice2> = Decoder::new(&buffer);
+        assert_eq!(decoder.try_decode::<u8>(), Ok(7));
+        let view: SliceView<u64> = decoder.try_decode_borrowed().unwrap();
+
+        assert!(view.iter().eq([1, u64::MAX]));
+        assert_eq!(SliceView::<u64>::new(&buffer[1..]).map(|view| view.len()), None);
+    }
+
+    #[test]
+    fn truncated_views_are_rejected() {
+        // A sequence of 2 `i32`s, which is missing its last byte.
+        let buffer: &[u8] = &[8, 1, 0, 0, 0, 2, 0, 0];
+        let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+
+        let error = decoder.try_decode_borrowed::<SliceView<i32>>().unwrap_err();
+        assert!(matches!(error.kind(), DecodeErrorKind::InvalidData { .. }));
+        assert_eq!(error.offset(), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn huge_views_from_a_reader_do_not_overflow() {
+        use crate::io_types::reader::ReadBuffer;
+
+        // A length of `VARUINT62_MAX`, which is too large for its elements to fit in memory.
+        let payload: &[u8] = &[0xFF; 8];
+        let buffer = ReadBuffer::new();
+        let mut decoder: Decoder<Slice2> = Decoder::from_reader(payload, &buffer);
+
+        let error = decoder.try_decode_borrowed::<SliceView<f64>>().unwrap_err();
+        assert!(matches!(error.kind(), DecodeErrorKind::InvalidData { .. }));
+        assert_eq!(error.offset(), 0);
+    }
+}
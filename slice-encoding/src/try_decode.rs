@@ -3,10 +3,23 @@
 use crate::decoder::{DecodeResult, Decoder};
 use crate::Encoding;
 
+#[cfg(feature = "alloc")]
+use crate::decoder::DecodeContext;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
 /// TODO
 pub trait TryDecode<E: Encoding>
     where Self: Sized,
 {
+    /// The minimum number of bytes that a value of this type can be encoded on.
+    ///
+    /// Decoders use this to reject collections whose length couldn't possibly fit in the remaining bytes, before
+    /// allocating any storage for them. See [`Decoder::try_decode_collection_length`].
+    ///
+    /// This defaults to `0`, which is always correct, but disables this check for collections of this type.
+    const MIN_ENCODED_SIZE: usize = 0;
+
     /// TODO
     fn try_decode(decoder: &mut Decoder<E>) -> DecodeResult<Self>;
 
@@ -14,6 +27,23 @@ pub trait TryDecode<E: Encoding>
     fn decode(decoder: &mut Decoder<E>) -> Self {
         default_error_handler(Self::try_decode(decoder))
     }
+
+    /// Decodes `length`-many values of this type, and returns them in a vector.
+    /// This is used for decoding the elements of a sequence, after its length has already been decoded.
+    ///
+    /// By default, this calls [`try_decode`](Self::try_decode) once per element. Types with a fixed-size encoding
+    /// override it to validate and read all the elements' bytes at once, which is much faster for large sequences.
+    ///
+    /// Note that this function doesn't check the decoder's allocation limit; callers are expected to have done so.
+    #[cfg(feature = "alloc")]
+    fn try_decode_elements(decoder: &mut Decoder<E>, length: usize) -> DecodeResult<Vec<Self>> {
+        let mut vector = Vec::with_capacity(length);
+        for i in 0..length {
+            let element = Self::try_decode(decoder).at_index(i)?;
+            vector.push(element);
+        }
+        Ok(vector)
+    }
 }
 
 /// TODO
@@ -29,6 +59,39 @@ pub trait TryDecodeCollection<E: Encoding, T>
     }
 }
 
+/// A companion to [`TryDecode`] for types which borrow their data directly from the decoder's input, instead of
+/// copying it into a new allocation. The lifetime `'a` is the lifetime of the decoder's input.
+///
+/// This is implemented for `&'a str`, `&'a [u8]`, and (if the 'alloc' feature is set) their [`Cow`] counterparts.
+///
+/// [`Cow`]: alloc::borrow::Cow
+///
+/// # Examples
+///
+/// ```
+/// # use slice_encoding::decoder::Decoder;
+/// # use slice_encoding::slice2::Slice2;
+/// let buffer: &[u8] = &[12, b'a', b'b', b'c'];
+/// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+///
+/// let string: &str = decoder.try_decode_borrowed().unwrap();
+/// assert_eq!(string, "abc");
+///
+/// // The string points directly into `buffer`; nothing was copied.
+/// assert!(core::ptr::eq(string.as_bytes(), &buffer[1..]));
+/// ```
+pub trait TryDecodeBorrowed<'a, E: Encoding>
+    where Self: Sized + 'a,
+{
+    /// Decodes a value of this type, borrowing its data from the decoder's input.
+    fn try_decode_borrowed(decoder: &mut Decoder<'a, E>) -> DecodeResult<Self>;
+
+    /// Equivalent to [`try_decode_borrowed`](Self::try_decode_borrowed), but panics if decoding fails.
+    fn decode_borrowed(decoder: &mut Decoder<'a, E>) -> Self {
+        default_error_handler(Self::try_decode_borrowed(decoder))
+    }
+}
+
 /// TODO
 pub type DecodeFn<T, E> = fn(&mut Decoder<E>) -> DecodeResult<T>;
 
@@ -14,6 +14,20 @@ pub trait TryEncode<E: Encoding>
     fn encode(self, encoder: &mut Encoder<E>) {
         default_error_handler(self.try_encode(encoder))
     }
+
+    /// Encodes each of the provided values in order.
+    /// This is used for encoding the elements of a sequence, after its length has already been encoded.
+    ///
+    /// By default, this calls [`try_encode`](Self::try_encode) once per element. Types with a fixed-size encoding
+    /// override it to reserve space for all the elements at once, which is much faster for large sequences.
+    fn try_encode_elements<I>(elements: I, encoder: &mut Encoder<E>) -> EncodeResult<()>
+        where I: ExactSizeIterator<Item = Self>,
+    {
+        for element in elements {
+            element.try_encode(encoder)?;
+        }
+        Ok(())
+    }
 }
 
 /// TODO
@@ -0,0 +1,53 @@
+// Copyright (c) ZeroC, Inc.
+
+use crate::decoder::{DecodeResult, Decoder};
+use crate::Encoding;
+
+/// Advances a decoder past an encoded value of this type, without decoding it, or allocating any memory.
+///
+/// This is useful for readers which don't care about certain values, or which encounter values they don't know how to
+/// decode (such as unknown tagged fields). Where possible, implementations use size prefixes and fixed widths to skip
+/// values in constant time. Note that skipped values are not validated.
+///
+/// `Self` can be unsized, so that types like `str` and `[T]` can be skipped, even though they can't be decoded.
+///
+/// # Examples
+///
+/// Structs can be skipped by skipping each of their fields in order:
+///
+#[cfg_attr(not(feature = "alloc"), doc = "```ignore")]
+#[cfg_attr(feature = "alloc", doc = "```")]
+/// # use slice_encoding::decoder::{Decoder, DecodeResult};
+/// # use slice_encoding::slice2::Slice2;
+/// # use slice_encoding::try_skip::TrySkip;
+/// struct Item {
+///     name: String,
+///     tags: Vec<String>,
+///     in_stock: bool,
+/// }
+///
+/// impl TrySkip<Slice2> for Item {
+///     fn try_skip(decoder: &mut Decoder<Slice2>) -> DecodeResult<()> {
+///         decoder.skip::<String>()?;
+///         decoder.skip::<Vec<String>>()?;
+///         decoder.skip::<bool>()
+///     }
+/// }
+///
+/// // An item named "a", with the tags `["b", "c"]`, followed by an `i32`.
+/// let buffer: &[u8] = &[4, b'a', 8, 4, b'b', 4, b'c', 1, 7, 0, 0, 0];
+/// let mut decoder: Decoder<Slice2> = Decoder::new(buffer);
+///
+/// decoder.skip::<Item>().unwrap();
+/// assert_eq!(decoder.try_decode::<i32>(), Ok(7));
+/// ```
+pub trait TrySkip<E: Encoding> {
+    /// The number of bytes every value of this type is encoded on, if this type has a fixed-size encoding.
+    ///
+    /// Sequences of types with a fixed size are skipped in constant time, instead of skipping each element in turn.
+    /// This defaults to `None`, which is always correct.
+    const FIXED_SIZE: Option<usize> = None;
+
+    /// Advances the decoder past an encoded value of this type.
+    fn try_skip(decoder: &mut Decoder<E>) -> DecodeResult<()>;
+}